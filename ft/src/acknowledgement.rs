@@ -0,0 +1,171 @@
+//! Optional guard against transfers to contracts that can't handle ADH. While the owner enables
+//! it, a transfer to an account that looks like a contract only succeeds once that account has
+//! called `acknowledge_token`.
+//!
+//! Contracts can't be told apart from user accounts on chain, so by default the guard relies on
+//! naming: implicit accounts and named accounts directly under a top-level account, like
+//! `alice.near`, are treated as users and bypass it. Deeper accounts, like `pool.alice.near`, are
+//! treated as contracts. Naming alone misclassifies contracts like `wrap.near`, so the owner can
+//! classify any account explicitly with `set_contract_account`, which takes precedence.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Records that the caller is willing to receive ADH. The caller must be registered and
+    /// attach at least 1 yoctoNEAR, plus the storage cost of the acknowledgement; the excess is
+    /// refunded.
+    #[payable]
+    pub fn acknowledge_token(&mut self) {
+        let account_id = env::predecessor_account_id();
+        self.assert_registered(&account_id);
+        let initial_storage_usage = env::storage_usage();
+        if self.acknowledged_receivers.insert(&account_id) {
+            log!("@{} acknowledged the token", account_id);
+        }
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    pub fn is_acknowledged_receiver(&self, account_id: AccountId) -> bool {
+        self.acknowledged_receivers.contains(&account_id)
+    }
+
+    /// Enables or disables the acknowledgement requirement for transfers to contract accounts.
+    pub fn set_require_receiver_acknowledgement(&mut self, required: bool) {
+        self.assert_owner();
+        self.require_receiver_acknowledgement = required;
+        log!(
+            "Receiver acknowledgement {}",
+            if required { "required" } else { "no longer required" }
+        );
+    }
+
+    pub fn requires_receiver_acknowledgement(&self) -> bool {
+        self.require_receiver_acknowledgement
+    }
+
+    /// Classifies `account_id` as a contract or a user account for the acknowledgement guard.
+    /// `None` goes back to classifying it by its name.
+    pub fn set_contract_account(&mut self, account_id: AccountId, is_contract: Option<bool>) {
+        self.assert_owner();
+        match is_contract {
+            Some(is_contract) => self.contract_accounts.insert(&account_id, &is_contract),
+            None => self.contract_accounts.remove(&account_id),
+        };
+    }
+
+    /// Returns whether transfers to `account_id` are treated as transfers to a contract.
+    pub fn is_contract_account(&self, account_id: AccountId) -> bool {
+        self.contract_accounts.get(&account_id).unwrap_or_else(|| !is_user_account(&account_id))
+    }
+}
+
+impl Contract {
+    pub(crate) fn check_receiver_acknowledgement(
+        &self,
+        receiver_id: &AccountId,
+    ) -> Result<(), String> {
+        if self.require_receiver_acknowledgement
+            && self.is_contract_account(receiver_id.clone())
+            && !self.acknowledged_receivers.contains(receiver_id)
+        {
+            return Err(format!("The contract {} has not acknowledged the token", receiver_id));
+        }
+        Ok(())
+    }
+}
+
+/// The naming rule: implicit accounts and accounts with at most one dot are users.
+fn is_user_account(account_id: &AccountId) -> bool {
+    let account_id = account_id.as_str();
+    let is_implicit = account_id.len() == 64
+        && account_id.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f'));
+    is_implicit || account_id.matches('.').count() <= 1
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn pool() -> AccountId {
+        "pool.danny.near".parse().unwrap()
+    }
+
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, pool());
+        register(&mut contract, accounts(2));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.set_require_receiver_acknowledgement(true);
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract pool.danny.near has not acknowledged the token")]
+    fn test_transfer_to_unacknowledged_contract() {
+        let mut contract = setup();
+        contract.ft_transfer(pool(), U128(10), None);
+    }
+
+    #[test]
+    fn test_transfer_to_acknowledged_contract() {
+        let mut contract = setup();
+        testing_env!(get_context(pool())
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.acknowledge_token();
+        assert!(contract.is_acknowledged_receiver(pool()));
+
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer(pool(), U128(10), None);
+        assert_eq!(contract.ft_balance_of(pool()).0, 10);
+    }
+
+    #[test]
+    fn test_user_accounts_bypass_acknowledgement() {
+        let mut contract = setup();
+        contract.ft_transfer(accounts(2), U128(10), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 10);
+
+        contract.set_require_receiver_acknowledgement(false);
+        contract.ft_transfer(pool(), U128(10), None);
+        assert_eq!(contract.ft_balance_of(pool()).0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract charlie has not acknowledged the token")]
+    fn test_account_classified_as_contract() {
+        let mut contract = setup();
+        contract.set_contract_account(accounts(2), Some(true));
+        assert!(contract.is_contract_account(accounts(2)));
+        contract.ft_transfer(accounts(2), U128(10), None);
+    }
+
+    #[test]
+    fn test_account_classified_as_user() {
+        let mut contract = setup();
+        contract.set_contract_account(pool(), Some(false));
+        contract.ft_transfer(pool(), U128(10), None);
+        assert_eq!(contract.ft_balance_of(pool()).0, 10);
+
+        contract.set_contract_account(pool(), None);
+        assert!(contract.is_contract_account(pool()));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account eugene is not registered")]
+    fn test_unregistered_acknowledgement() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(4)).attached_deposit(ONE_NEAR).build());
+        contract.acknowledge_token();
+    }
+}
@@ -0,0 +1,145 @@
+//! Merkle airdrops. The owner publishes the root of a tree of `(account_id, amount)` allocations
+//! and reserves their total; each listed account then claims its own allocation with a proof.
+//!
+//! A leaf is the SHA-256 of the Borsh serialization of `(account_id, amount)`, and a parent is
+//! the SHA-256 of its two children concatenated smallest first, so proofs need no left/right
+//! flags. The reserve is held in custody on the contract's own account until claimed.
+use near_sdk::json_types::Base64VecU8;
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Opens a new airdrop round with the Merkle `root` of its allocations, moving `total` from
+    /// the owner's balance into the reserve. Whatever is left of the previous round goes back to
+    /// the owner.
+    pub fn set_airdrop_root(&mut self, root: Base64VecU8, total: U128) {
+        self.assert_owner();
+        assert!(root.0.len() == 32, "The root should be a 32 byte SHA-256 hash");
+        let owner_id = self.owner_id.clone();
+        let contract_id = self.register_custody_account();
+        if self.airdrop_remaining > 0 {
+            let remaining = std::mem::take(&mut self.airdrop_remaining);
+            self.internal_transfer(
+                &contract_id,
+                &owner_id,
+                remaining,
+                Some("Unclaimed airdrop".to_string()),
+            );
+        }
+        if total.0 > 0 {
+            self.internal_transfer(&owner_id, &contract_id, total.0, Some("Airdrop".to_string()));
+        }
+        self.airdrop_root = Some(root.0);
+        self.airdrop_round += 1;
+        self.airdrop_remaining = total.0;
+        log!("Opened airdrop round {} with {}", self.airdrop_round, total.0);
+    }
+
+    /// Claims the caller's allocation of `amount` in the current round, proven by the sibling
+    /// hashes from its leaf up to the root.
+    pub fn claim_airdrop(&mut self, amount: U128, proof: Vec<Base64VecU8>) {
+        let account_id = env::predecessor_account_id();
+        let root = self.airdrop_root.clone().expect("No airdrop is open");
+        assert!(!self.has_claimed(account_id.clone()), "The airdrop is already claimed");
+        let leaf = env::sha256(&(account_id.clone(), amount.0).try_to_vec().unwrap());
+        let computed = proof.iter().fold(leaf, |node, sibling| {
+            if node <= sibling.0 {
+                env::sha256(&[node, sibling.0.clone()].concat())
+            } else {
+                env::sha256(&[sibling.0.clone(), node].concat())
+            }
+        });
+        assert!(computed == root, "Invalid airdrop proof");
+        assert!(self.airdrop_remaining >= amount.0, "The airdrop is exhausted");
+        self.assert_registered(&account_id);
+        self.airdrop_claimed.insert(&(self.airdrop_round, account_id.clone()));
+        self.airdrop_remaining -= amount.0;
+        self.internal_transfer(
+            &env::current_account_id(),
+            &account_id,
+            amount.0,
+            Some("Airdrop".to_string()),
+        );
+    }
+
+    /// Returns whether `account_id` claimed its allocation in the current round.
+    pub fn has_claimed(&self, account_id: AccountId) -> bool {
+        self.airdrop_claimed.contains(&(self.airdrop_round, account_id))
+    }
+
+    pub fn airdrop_remaining(&self) -> U128 {
+        self.airdrop_remaining.into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn leaf(account_id: AccountId, amount: Balance) -> Vec<u8> {
+        env::sha256(&(account_id, amount).try_to_vec().unwrap())
+    }
+
+    /// Bob airdrops 120 to a tree allocating 100 to charlie and 50 to danny.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        register(&mut contract, accounts(3));
+        let (charlie, danny) = (leaf(accounts(2), 100), leaf(accounts(3), 50));
+        let root = if charlie <= danny {
+            env::sha256(&[charlie, danny].concat())
+        } else {
+            env::sha256(&[danny, charlie].concat())
+        };
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.set_airdrop_root(root.into(), U128(120));
+        contract
+    }
+
+    fn claim(contract: &mut Contract, claimant: AccountId, amount: Balance, sibling: Vec<u8>) {
+        testing_env!(get_context(claimant).storage_usage(env::storage_usage()).build());
+        contract.claim_airdrop(U128(amount), vec![Base64VecU8(sibling)]);
+    }
+
+    #[test]
+    fn test_claim_airdrop() {
+        let mut contract = setup();
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 120);
+        claim(&mut contract, accounts(2), 100, leaf(accounts(3), 50));
+        assert!(contract.has_claimed(accounts(2)));
+        assert!(!contract.has_claimed(accounts(3)));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 100);
+        assert_eq!(contract.airdrop_remaining().0, 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "The airdrop is already claimed")]
+    fn test_double_claim() {
+        let mut contract = setup();
+        claim(&mut contract, accounts(2), 100, leaf(accounts(3), 50));
+        claim(&mut contract, accounts(2), 100, leaf(accounts(3), 50));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid airdrop proof")]
+    fn test_claim_more_than_allocated() {
+        let mut contract = setup();
+        claim(&mut contract, accounts(2), 101, leaf(accounts(3), 50));
+    }
+
+    #[test]
+    #[should_panic(expected = "The airdrop is exhausted")]
+    fn test_claim_beyond_reserve() {
+        let mut contract = setup();
+        claim(&mut contract, accounts(2), 100, leaf(accounts(3), 50));
+        claim(&mut contract, accounts(3), 50, leaf(accounts(2), 100));
+    }
+}
@@ -0,0 +1,168 @@
+//! ERC-20 style approvals for integrations that pull tokens with `ft_transfer_from`. This is
+//! additive: the NEAR-native way to pay a contract remains `ft_transfer_call`, which needs no
+//! standing allowance.
+use near_sdk::assert_one_yocto;
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the amount `spender` may transfer out of the caller's balance, replacing any previous
+    /// allowance. Zero revokes it. Requires at least 1 yoctoNEAR, plus the storage cost of a new
+    /// allowance; the excess is refunded.
+    #[payable]
+    pub fn ft_approve(&mut self, spender: AccountId, amount: U128) {
+        let initial_storage_usage = env::storage_usage();
+        self.internal_approve(env::predecessor_account_id(), spender, amount.into());
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Transfers `amount` from `owner` to `receiver_id` on behalf of the caller, consuming its
+    /// allowance. The owner pays the transfer fee out of `amount`. A blacklisted spender can't
+    /// move tokens either. Requires 1 yoctoNEAR.
+    #[payable]
+    pub fn ft_transfer_from(
+        &mut self,
+        owner: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let spender = env::predecessor_account_id();
+        self.assert_not_blacklisted(&[&spender]);
+        let key = (owner.clone(), spender);
+        let allowance = self.allowances.get(&key).unwrap_or(0);
+        assert!(allowance >= amount.0, "Insufficient allowance");
+        assert!(owner != receiver_id, "Sender and receiver should be different");
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        self.assert_registered(&receiver_id);
+        self.assert_transfer(&owner, &receiver_id, amount.0);
+        if allowance == amount.0 {
+            self.allowances.remove(&key);
+        } else {
+            self.allowances.insert(&key, &(allowance - amount.0));
+        }
+        let received =
+            self.internal_transfer_with_fee(&owner, &receiver_id, amount.0, memo.clone());
+        self.record_receipt(&owner, &receiver_id, received, memo);
+    }
+
+    pub fn ft_allowance(&self, owner: AccountId, spender: AccountId) -> U128 {
+        self.allowances.get(&(owner, spender)).unwrap_or(0).into()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_approve(
+        &mut self,
+        owner: AccountId,
+        spender: AccountId,
+        amount: Balance,
+    ) {
+        self.assert_registered(&owner);
+        let key = (owner, spender);
+        if amount == 0 {
+            self.allowances.remove(&key);
+        } else {
+            self.allowances.insert(&key, &amount);
+        }
+        log!("@{} approved @{} to spend {}", key.0, key.1, amount);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    /// Bob approves danny to spend 100 and danny is the caller.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.ft_approve(accounts(3), U128(100));
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(3)).build());
+        contract
+    }
+
+    #[test]
+    fn test_transfer_from_consumes_allowance() {
+        let mut contract = setup();
+        contract.ft_transfer_from(accounts(1), accounts(2), U128(60), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 60);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 60);
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(3)).0, 40);
+
+        contract.ft_transfer_from(accounts(1), accounts(2), U128(40), None);
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(3)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient allowance")]
+    fn test_transfer_from_above_allowance() {
+        let mut contract = setup();
+        contract.ft_transfer_from(accounts(1), accounts(2), U128(101), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn test_transfer_from_above_balance() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.ft_approve(accounts(3), U128(TOTAL_SUPPLY + 1));
+        testing_env!(get_context(accounts(3)).attached_deposit(1).build());
+        contract.ft_transfer_from(accounts(1), accounts(2), U128(TOTAL_SUPPLY + 1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is blacklisted")]
+    fn test_transfer_from_by_blacklisted_spender() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1)).build());
+        contract.blacklist_account(accounts(3));
+        testing_env!(get_context(accounts(3)).attached_deposit(1).build());
+        contract.ft_transfer_from(accounts(1), accounts(2), U128(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_transfer_from_without_deposit() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(3)).build());
+        contract.ft_transfer_from(accounts(1), accounts(2), U128(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn test_approve_without_deposit() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1)).build());
+        contract.ft_approve(accounts(2), U128(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_new_allowance_needs_storage() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.ft_approve(accounts(2), U128(1));
+    }
+
+    #[test]
+    fn test_updating_allowance_keeps_one_yocto() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.ft_approve(accounts(3), U128(50));
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(3)).0, 50);
+        assert!(get_created_receipts().is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+//! Transfers from one sender to many receivers in a single call, e.g. for airdrops.
+use near_contract_standards::fungible_token::events::FtTransfer;
+use near_sdk::assert_one_yocto;
+
+use crate::*;
+
+/// Longest batch `ft_transfer_batch` accepts. Besides moving balances, each transfer updates the
+/// balance snapshots, delegated votes and receipts of its receiver, and 50 of them stay well
+/// within the 300 TGas a single call can use. Larger airdrops should be split across calls.
+pub const MAX_BATCH_TRANSFERS: usize = 50;
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers every `(receiver_id, amount)` of `transfers` from the caller. The whole batch is
+    /// validated before any balance moves, so it either succeeds as a whole or fails without
+    /// effect. Each receiver gets its amount less the transfer fee. A single `ft_transfer` event
+    /// lists all the transfers. Requires 1 yoctoNEAR.
+    #[payable]
+    pub fn ft_transfer_batch(&mut self, transfers: Vec<(AccountId, U128)>, memo: Option<String>) {
+        assert_one_yocto();
+        assert!(!transfers.is_empty(), "The batch is empty");
+        assert!(
+            transfers.len() <= MAX_BATCH_TRANSFERS,
+            "The batch exceeds the maximum of {} transfers",
+            MAX_BATCH_TRANSFERS
+        );
+        let sender_id = env::predecessor_account_id();
+        self.assert_registered(&sender_id);
+        let mut total: Balance = 0;
+        for (receiver_id, amount) in &transfers {
+            assert!(&sender_id != receiver_id, "Sender and receiver should be different");
+            assert!(amount.0 > 0, "The amount should be a positive number");
+            self.assert_registered(receiver_id);
+            self.assert_transfer(&sender_id, receiver_id, amount.0);
+            self.amount_after_fee(amount.0);
+            total = total.checked_add(amount.0).expect("Balance overflow");
+        }
+        // Each transfer was checked on its own; the batch as a whole must also stay within the
+        // balance outside the sender's earmarks.
+        self.assert_free_balance(&sender_id, total);
+
+        let mut received = Vec::with_capacity(transfers.len());
+        for (receiver_id, amount) in &transfers {
+            let amount_received = self.amount_after_fee(amount.0);
+            self.token.internal_withdraw(&sender_id, amount_received);
+            self.token.internal_deposit(receiver_id, amount_received);
+            self.record_balance_snapshots(&[receiver_id]);
+            self.move_voting_power(Some(&sender_id), Some(receiver_id), amount_received);
+            self.record_receipt(&sender_id, receiver_id, amount_received, memo.clone());
+            received.push((receiver_id, U128(amount_received)));
+        }
+        self.record_balance_snapshots(&[&sender_id]);
+        let events: Vec<FtTransfer> = received
+            .iter()
+            .map(|(receiver_id, amount)| FtTransfer {
+                old_owner_id: &sender_id,
+                new_owner_id: receiver_id,
+                amount,
+                memo: memo.as_deref(),
+            })
+            .collect();
+        FtTransfer::emit_many(&events);
+        for (_, amount) in &transfers {
+            self.charge_transfer_fee(&sender_id, amount.0);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::testing_env;
+
+    use super::MAX_BATCH_TRANSFERS;
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        register(&mut contract, accounts(3));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract
+    }
+
+    #[test]
+    fn test_transfer_batch() {
+        let mut contract = setup();
+        contract.ft_transfer_batch(
+            vec![(accounts(2), U128(10)), (accounts(3), U128(20)), (accounts(2), U128(5))],
+            Some("airdrop".to_string()),
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 35);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 15);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 20);
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"charlie","amount":"10","memo":"airdrop"},{"old_owner_id":"bob","new_owner_id":"danny","amount":"20","memo":"airdrop"},{"old_owner_id":"bob","new_owner_id":"charlie","amount":"5","memo":"airdrop"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The account eugene is not registered")]
+    fn test_transfer_batch_unregistered_receiver() {
+        let mut contract = setup();
+        contract.ft_transfer_batch(vec![(accounts(2), U128(10)), (accounts(4), U128(10))], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn test_transfer_batch_insufficient_balance() {
+        let mut contract = setup();
+        contract.ft_transfer_batch(
+            vec![(accounts(2), U128(TOTAL_SUPPLY)), (accounts(3), U128(1))],
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The batch exceeds the maximum of 50 transfers")]
+    fn test_transfer_batch_too_long() {
+        let mut contract = setup();
+        contract.ft_transfer_batch(vec![(accounts(2), U128(1)); MAX_BATCH_TRANSFERS + 1], None);
+    }
+}
@@ -0,0 +1,94 @@
+//! Freezing of sanctioned accounts. A blacklisted account can neither send nor receive tokens,
+//! whichever route the transfer takes.
+use crate::*;
+
+const BLACKLISTED: &str = "Account is blacklisted";
+
+#[near_bindgen]
+impl Contract {
+    pub fn blacklist_account(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.blacklist.insert(&account_id) {
+            log!("Blacklisted @{}", account_id);
+        }
+    }
+
+    pub fn unblacklist_account(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.blacklist.remove(&account_id) {
+            log!("Removed @{} from the blacklist", account_id);
+        }
+    }
+
+    pub fn is_blacklisted(&self, account_id: AccountId) -> bool {
+        self.blacklist.contains(&account_id)
+    }
+}
+
+impl Contract {
+    pub(crate) fn check_not_blacklisted(&self, account_ids: &[&AccountId]) -> Result<(), String> {
+        if account_ids.iter().any(|account_id| self.blacklist.contains(account_id)) {
+            return Err(BLACKLISTED.to_string());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn assert_not_blacklisted(&self, account_ids: &[&AccountId]) {
+        if let Err(message) = self.check_not_blacklisted(account_ids) {
+            panic!("{}", message);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.blacklist_account(accounts(2));
+        assert!(contract.is_blacklisted(accounts(2)));
+        testing_env!(context.attached_deposit(1).build());
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is blacklisted")]
+    fn test_transfer_to_blacklisted() {
+        let mut contract = setup();
+        contract.ft_transfer(accounts(2), U128(10), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is blacklisted")]
+    fn test_transfer_call_from_blacklisted() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2)).attached_deposit(1).build());
+        contract.ft_transfer_call(accounts(1), U128(10), None, String::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is blacklisted")]
+    fn test_mint_to_blacklisted() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1)).build());
+        contract.ft_mint(accounts(2), U128(10), None);
+    }
+
+    #[test]
+    fn test_unblacklist_restores_transfers() {
+        let mut contract = setup();
+        contract.unblacklist_account(accounts(2));
+        assert!(!contract.is_blacklisted(accounts(2)));
+        contract.ft_transfer(accounts(2), U128(10), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 10);
+    }
+}
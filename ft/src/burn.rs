@@ -0,0 +1,113 @@
+//! Destroying ADH, e.g. after a governance buyback.
+use near_contract_standards::fungible_token::events::FtBurn;
+use near_sdk::assert_one_yocto;
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Burns `amount` of the caller's tokens, reducing the total supply. Requires 1 yoctoNEAR.
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        self.assert_not_blacklisted(&[&account_id]);
+        self.internal_burn(&account_id, amount.into(), memo.as_deref());
+    }
+
+    /// Burns `amount` from `account_id`, e.g. a treasury account's tokens after a buyback. Only the
+    /// owner can burn from another account. The contract's own account is excluded: everything on
+    /// it is held in custody for vesting, locks, stakes, rewards and airdrops. Requires 1
+    /// yoctoNEAR.
+    #[payable]
+    pub fn ft_burn_from(&mut self, account_id: AccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(
+            account_id != env::current_account_id(),
+            "Tokens held in custody by the contract can't be burned"
+        );
+        self.internal_burn(&account_id, amount.into(), memo.as_deref());
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_burn(
+        &mut self,
+        account_id: &AccountId,
+        amount: Balance,
+        memo: Option<&str>,
+    ) {
+        assert!(amount > 0, "The amount should be a positive number");
+        self.assert_registered(account_id);
+        self.assert_free_balance(account_id, amount);
+        self.token.internal_withdraw(account_id, amount);
+        self.record_balance_snapshots(&[account_id]);
+        self.move_voting_power(Some(account_id), None, amount);
+        FtBurn { owner_id: account_id, amount: &U128(amount), memo }.emit();
+        self.on_tokens_burned(account_id.clone(), amount);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    #[test]
+    fn test_burn() {
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.ft_burn(U128(100), Some("buyback".to_string()));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 100);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 100);
+        assert_eq!(
+            get_logs()[1..],
+            [
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"bob","amount":"100","memo":"buyback"}]}"#,
+                r#"EVENT_JSON:{"standard":"adashi","version":"1.0.0","event":"tokens_burned","data":[{"account_id":"bob","amount":"100"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn test_burn_above_balance() {
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.ft_burn(U128(TOTAL_SUPPLY + 1), None);
+    }
+
+    #[test]
+    fn test_burn_from() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), U128(100), None);
+        contract.ft_burn_from(accounts(2), U128(40), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 60);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_burn_from_by_non_owner() {
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        testing_env!(get_context(accounts(2)).attached_deposit(1).build());
+        contract.ft_burn_from(accounts(1), U128(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tokens held in custody by the contract can't be burned")]
+    fn test_burn_from_custody() {
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.ft_burn_from(accounts(0), U128(1), None);
+    }
+}
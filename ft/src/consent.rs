@@ -0,0 +1,148 @@
+//! Opt-in receive consent. An account can set a threshold above which it only accepts transfers
+//! from senders it has explicitly granted consent to, protecting it from unwanted dusting.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Requires prior consent for incoming transfers strictly above `threshold`. A zero threshold
+    /// removes the requirement. Requires at least 1 yoctoNEAR, plus the storage cost of a new
+    /// threshold; the excess is refunded.
+    #[payable]
+    pub fn set_receive_consent_threshold(&mut self, threshold: U128) {
+        let account_id = env::predecessor_account_id();
+        self.assert_registered(&account_id);
+        let initial_storage_usage = env::storage_usage();
+        if threshold.0 == 0 {
+            self.receive_consent_thresholds.remove(&account_id);
+        } else {
+            self.receive_consent_thresholds.insert(&account_id, &threshold.0);
+        }
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Allows `sender_id` to send the caller transfers above the caller's consent threshold, and
+    /// timelocked transfers of any amount. Requires at least 1 yoctoNEAR, plus the storage cost of
+    /// a new consent; the excess is refunded.
+    #[payable]
+    pub fn grant_receive_consent(&mut self, sender_id: AccountId) {
+        let account_id = env::predecessor_account_id();
+        self.assert_registered(&account_id);
+        let initial_storage_usage = env::storage_usage();
+        self.receive_consents.insert(&(account_id, sender_id));
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    pub fn revoke_receive_consent(&mut self, sender_id: AccountId) {
+        self.receive_consents.remove(&(env::predecessor_account_id(), sender_id));
+    }
+
+    pub fn receive_consent_threshold(&self, account_id: AccountId) -> Option<U128> {
+        self.receive_consent_thresholds.get(&account_id).map(|threshold| threshold.into())
+    }
+
+    pub fn has_receive_consent(&self, account_id: AccountId, sender_id: AccountId) -> bool {
+        self.receive_consents.contains(&(account_id, sender_id))
+    }
+}
+
+impl Contract {
+    pub(crate) fn check_receive_consent(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Result<(), String> {
+        match self.receive_consent_thresholds.get(receiver_id) {
+            Some(threshold)
+                if amount > threshold
+                    && !self
+                        .receive_consents
+                        .contains(&(receiver_id.clone(), sender_id.clone())) =>
+            {
+                Err(format!(
+                    "Receiver {} has not consented to transfers above {} from {}",
+                    receiver_id, threshold, sender_id
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.set_receive_consent_threshold(U128(100));
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(2)).build());
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver bob has not consented to transfers above 100 from charlie")]
+    fn test_over_threshold_without_consent() {
+        let mut contract = setup();
+        contract.ft_transfer(accounts(1), U128(101), None);
+    }
+
+    #[test]
+    fn test_over_threshold_with_consent() {
+        let mut contract = setup();
+        let mut context = get_context(accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.grant_receive_consent(accounts(2));
+        assert!(contract.has_receive_consent(accounts(1), accounts(2)));
+
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(2)).build());
+        contract.ft_transfer(accounts(1), U128(1_000), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_consent_needs_storage() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.grant_receive_consent(accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_threshold_needs_storage() {
+        let mut contract = setup();
+        contract.set_receive_consent_threshold(U128(100));
+    }
+
+    #[test]
+    fn test_under_threshold_always_allowed() {
+        let mut contract = setup();
+        contract.ft_transfer(accounts(1), U128(100), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(
+            contract.simulate_transfers(vec![(accounts(2), accounts(1), U128(101))]),
+            vec![Err(
+                "Receiver bob has not consented to transfers above 100 from charlie".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Delegation of voting power. Holders keep custody of their ADH and assign its voting weight to a
+//! delegatee, whose votes are the sum of the live balances of everyone delegating to it. As with
+//! COMP, an account that has not delegated (possibly to itself) contributes no votes.
+//!
+//! To stop flash delegations right before a vote, the owner can set a minimum delegation age:
+//! a delegator's balance only counts toward its delegatee's votes once the delegation is at least
+//! that old. Re-delegating restarts the clock. A delegatee can have at most
+//! `MAX_RECENT_DELEGATORS` delegations younger than the minimum age at a time, so `get_votes`
+//! stays cheap; delegating to it fails until older ones age out.
+//!
+//! Delegating to this contract's own account abstains: the holder's power is removed from any
+//! tally without being credited to anyone, until it delegates elsewhere.
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Caps the delegations younger than the minimum age one delegatee can have.
+pub const MAX_RECENT_DELEGATORS: usize = 50;
+
+/// How the votes of an account are derived. This token has no stake boosts, vote-escrow locks or
+/// suspensions, so those components are always zero.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VotingPowerBreakdown {
+    /// The account's own balance, when it delegates to itself.
+    pub own_balance_power: U128,
+    /// Votes delegated to the account by others.
+    pub delegated_in: U128,
+    /// The account's balance, when it delegates to another account.
+    pub delegated_out: U128,
+    pub stake_boost: U128,
+    pub ve_boost: U128,
+    /// The account's balance, when it abstains.
+    pub abstained: U128,
+    pub suspended: U128,
+    /// The account's votes, as returned by `get_votes`.
+    pub effective: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Delegates the caller's voting power to `delegatee`, moving it away from the previous
+    /// delegatee if any. Delegating to the contract's own account abstains. Requires a deposit
+    /// covering the storage of the delegation and of the votes it moves; any excess is refunded.
+    #[payable]
+    pub fn delegate(&mut self, delegatee: AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        let delegator = env::predecessor_account_id();
+        self.assert_registered(&delegator);
+        let balance = self.token.accounts.get(&delegator).unwrap_or(0);
+        let previous = self.delegates.insert(&delegator, &delegatee);
+        self.move_delegated_votes(previous.as_ref(), Some(&delegatee), balance);
+        if self.min_delegation_age_ms > 0 && delegatee != env::current_account_id() {
+            self.delegated_at.insert(&delegator, &env::block_timestamp());
+            let mut recent = self.recent_delegators.get(&delegatee).unwrap_or_default();
+            recent.retain(|account_id| {
+                account_id != &delegator && self.is_recent_delegation(account_id, &delegatee)
+            });
+            assert!(
+                recent.len() < MAX_RECENT_DELEGATORS,
+                "The account {} has too many recent delegations",
+                delegatee
+            );
+            recent.push(delegator.clone());
+            self.recent_delegators.insert(&delegatee, &recent);
+        }
+        log!("@{} delegated to @{}", delegator, delegatee);
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Returns the votes of `account_id`, leaving out delegations younger than the minimum age.
+    pub fn get_votes(&self, account_id: AccountId) -> U128 {
+        let votes = self.voting_power.get(&account_id).unwrap_or(0);
+        if self.min_delegation_age_ms == 0 {
+            return votes.into();
+        }
+        let too_recent: Balance = self
+            .recent_delegators
+            .get(&account_id)
+            .unwrap_or_default()
+            .iter()
+            .filter(|delegator| self.is_recent_delegation(delegator, &account_id))
+            .map(|delegator| self.token.accounts.get(delegator).unwrap_or(0))
+            .sum();
+        votes.saturating_sub(too_recent).into()
+    }
+
+    /// Splits the votes of `account_id` into the mechanisms they come from. `effective` always
+    /// equals `get_votes`.
+    pub fn voting_power_breakdown(&self, account_id: AccountId) -> VotingPowerBreakdown {
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        let effective = self.get_votes(account_id.clone()).0;
+        let delegatee = self.delegates.get(&account_id);
+        let share_if = |condition: bool| if condition { balance } else { 0 };
+        let abstained = share_if(delegatee == Some(env::current_account_id()));
+        let own_balance_power = share_if(
+            delegatee.as_ref() == Some(&account_id)
+                && !self.is_recent_delegation(&account_id, &account_id),
+        );
+        let delegated_out = share_if(delegatee.as_ref().is_some_and(|delegatee| {
+            *delegatee != account_id && *delegatee != env::current_account_id()
+        }));
+        VotingPowerBreakdown {
+            own_balance_power: own_balance_power.into(),
+            delegated_in: (effective - own_balance_power).into(),
+            delegated_out: delegated_out.into(),
+            stake_boost: 0.into(),
+            ve_boost: 0.into(),
+            abstained: abstained.into(),
+            suspended: 0.into(),
+            effective: effective.into(),
+        }
+    }
+
+    pub fn delegate_of(&self, account_id: AccountId) -> Option<AccountId> {
+        self.delegates.get(&account_id)
+    }
+
+    pub fn is_abstaining(&self, account_id: AccountId) -> bool {
+        self.delegates.get(&account_id) == Some(env::current_account_id())
+    }
+
+    /// Sets how old a delegation must be, in milliseconds, before it counts. Zero disables the
+    /// rule.
+    pub fn set_min_delegation_age(&mut self, min_delegation_age_ms: u64) {
+        self.assert_owner();
+        self.min_delegation_age_ms = min_delegation_age_ms;
+    }
+
+    pub fn min_delegation_age_ms(&self) -> u64 {
+        self.min_delegation_age_ms
+    }
+}
+
+impl Contract {
+    /// Follows a balance movement of `amount` from holder `from` to holder `to`, where `None`
+    /// stands for a mint or a burn.
+    pub(crate) fn move_voting_power(
+        &mut self,
+        from: Option<&AccountId>,
+        to: Option<&AccountId>,
+        amount: Balance,
+    ) {
+        let from_delegatee = from.and_then(|account_id| self.delegates.get(account_id));
+        let to_delegatee = to.and_then(|account_id| self.delegates.get(account_id));
+        self.move_delegated_votes(from_delegatee.as_ref(), to_delegatee.as_ref(), amount);
+    }
+
+    /// Whether `delegator` currently delegates to `delegatee` through a delegation younger than
+    /// the minimum age.
+    fn is_recent_delegation(&self, delegator: &AccountId, delegatee: &AccountId) -> bool {
+        self.delegates.get(delegator).as_ref() == Some(delegatee)
+            && self.delegated_at.get(delegator).is_some_and(|delegated_at| {
+                env::block_timestamp() < delegated_at + self.min_delegation_age_ms * 1_000_000
+            })
+    }
+
+    fn move_delegated_votes(
+        &mut self,
+        from: Option<&AccountId>,
+        to: Option<&AccountId>,
+        amount: Balance,
+    ) {
+        let abstain_target = env::current_account_id();
+        let from = from.filter(|account_id| **account_id != abstain_target);
+        let to = to.filter(|account_id| **account_id != abstain_target);
+        if amount == 0 || from == to {
+            return;
+        }
+        if let Some(from) = from {
+            let votes = self.voting_power.get(from).unwrap_or(0) - amount;
+            if votes == 0 {
+                self.voting_power.remove(from);
+            } else {
+                self.voting_power.insert(from, &votes);
+            }
+        }
+        if let Some(to) = to {
+            let votes = self.voting_power.get(to).unwrap_or(0) + amount;
+            self.voting_power.insert(to, &votes);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::{testing_env, Gas, PromiseResult, RuntimeFeesConfig, VMConfig};
+
+    use super::MAX_RECENT_DELEGATORS;
+    use crate::tests::{delegate_as, get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract
+    }
+
+    #[test]
+    fn test_delegate_and_redelegate() {
+        let mut contract = setup();
+        assert_eq!(contract.get_votes(accounts(1)).0, 0);
+        delegate_as(&mut contract, accounts(1), accounts(2));
+        assert_eq!(contract.delegate_of(accounts(1)), Some(accounts(2)));
+        assert_eq!(contract.get_votes(accounts(2)).0, TOTAL_SUPPLY);
+
+        delegate_as(&mut contract, accounts(1), accounts(3));
+        assert_eq!(contract.get_votes(accounts(2)).0, 0);
+        assert_eq!(contract.get_votes(accounts(3)).0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn test_delegate_requires_deposit() {
+        let mut contract = setup();
+        contract.delegate(accounts(2));
+    }
+
+    #[test]
+    fn test_votes_follow_transfers_and_mints() {
+        let mut contract = setup();
+        delegate_as(&mut contract, accounts(1), accounts(3));
+        delegate_as(&mut contract, accounts(2), accounts(2));
+
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), U128(300), None);
+        assert_eq!(contract.get_votes(accounts(3)).0, TOTAL_SUPPLY - 300);
+        assert_eq!(contract.get_votes(accounts(2)).0, 300);
+
+        contract.ft_mint(accounts(2), U128(50), None);
+        assert_eq!(contract.get_votes(accounts(2)).0, 350);
+    }
+
+    #[test]
+    fn test_votes_only_from_delegators() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), U128(300), None);
+        delegate_as(&mut contract, accounts(2), accounts(2));
+        assert_eq!(contract.get_votes(accounts(2)).0, 300);
+        assert_eq!(contract.get_votes(accounts(1)).0, 0);
+    }
+
+    #[test]
+    fn test_votes_follow_transfer_call_refunds() {
+        let mut contract = setup();
+        delegate_as(&mut contract, accounts(1), accounts(1));
+        delegate_as(&mut contract, accounts(2), accounts(2));
+        testing_env!(get_context(accounts(1))
+            .attached_deposit(1)
+            .prepaid_gas(Gas(300_000_000_000_000))
+            .build());
+        contract.ft_transfer_call(accounts(2), U128(300), None, String::new());
+        assert_eq!(contract.get_votes(accounts(2)).0, 300);
+
+        testing_env!(
+            get_context(accounts(0)).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"100\"".to_vec())],
+        );
+        contract.ft_resolve_transfer(accounts(1), accounts(2), U128(300));
+        assert_eq!(contract.get_votes(accounts(2)).0, 200);
+        assert_eq!(contract.get_votes(accounts(1)).0, TOTAL_SUPPLY - 200);
+    }
+
+    #[test]
+    fn test_min_delegation_age() {
+        let mut contract = setup();
+        contract.set_min_delegation_age(1_000);
+        testing_env!(get_context(accounts(1)).block_timestamp(5_000_000_000).build());
+        delegate_as(&mut contract, accounts(1), accounts(2));
+        assert_eq!(contract.get_votes(accounts(2)).0, 0);
+
+        testing_env!(get_context(accounts(1)).block_timestamp(6_000_000_000).build());
+        assert_eq!(contract.get_votes(accounts(2)).0, TOTAL_SUPPLY);
+
+        delegate_as(&mut contract, accounts(1), accounts(3));
+        assert_eq!(contract.get_votes(accounts(2)).0, 0);
+        assert_eq!(contract.get_votes(accounts(3)).0, 0);
+        contract.set_min_delegation_age(0);
+        assert_eq!(contract.get_votes(accounts(3)).0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_recent_delegators_age_out() {
+        let mut contract = setup();
+        contract.set_min_delegation_age(1_000);
+        for i in 0..MAX_RECENT_DELEGATORS {
+            let delegator: AccountId = format!("delegator{}.near", i).parse().unwrap();
+            register(&mut contract, delegator.clone());
+            delegate_as(&mut contract, delegator, accounts(2));
+        }
+        testing_env!(get_context(accounts(1)).block_timestamp(1_000_000_000).build());
+        delegate_as(&mut contract, accounts(1), accounts(2));
+        assert_eq!(contract.recent_delegators.get(&accounts(2)).unwrap().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account charlie has too many recent delegations")]
+    fn test_too_many_recent_delegators() {
+        let mut contract = setup();
+        contract.set_min_delegation_age(1_000);
+        for i in 0..MAX_RECENT_DELEGATORS {
+            let delegator: AccountId = format!("delegator{}.near", i).parse().unwrap();
+            register(&mut contract, delegator.clone());
+            delegate_as(&mut contract, delegator, accounts(2));
+        }
+        delegate_as(&mut contract, accounts(1), accounts(2));
+    }
+
+    #[test]
+    fn test_abstain() {
+        let mut contract = setup();
+        delegate_as(&mut contract, accounts(1), accounts(2));
+        delegate_as(&mut contract, accounts(1), accounts(0));
+        assert!(contract.is_abstaining(accounts(1)));
+        assert_eq!(contract.get_votes(accounts(2)).0, 0);
+        assert_eq!(contract.get_votes(accounts(0)).0, 0);
+
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), U128(300), None);
+        assert_eq!(contract.get_votes(accounts(0)).0, 0);
+
+        delegate_as(&mut contract, accounts(1), accounts(2));
+        assert!(!contract.is_abstaining(accounts(1)));
+        assert_eq!(contract.get_votes(accounts(2)).0, TOTAL_SUPPLY - 300);
+    }
+
+    #[test]
+    fn test_voting_power_breakdown() {
+        let mut contract = setup();
+        register(&mut contract, accounts(3));
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer(accounts(2), U128(300), None);
+        contract.ft_transfer(accounts(3), U128(200), None);
+        delegate_as(&mut contract, accounts(1), accounts(2));
+        delegate_as(&mut contract, accounts(2), accounts(2));
+        delegate_as(&mut contract, accounts(3), accounts(0));
+
+        let breakdown = contract.voting_power_breakdown(accounts(2));
+        assert_eq!(breakdown.own_balance_power.0, 300);
+        assert_eq!(breakdown.delegated_in.0, TOTAL_SUPPLY - 500);
+        assert_eq!(breakdown.effective, contract.get_votes(accounts(2)));
+        assert_eq!(breakdown.effective.0, TOTAL_SUPPLY - 200);
+
+        let breakdown = contract.voting_power_breakdown(accounts(1));
+        assert_eq!(breakdown.delegated_out.0, TOTAL_SUPPLY - 500);
+        assert_eq!(breakdown.effective.0, 0);
+        let breakdown = contract.voting_power_breakdown(accounts(3));
+        assert_eq!(breakdown.abstained.0, 200);
+        assert_eq!(breakdown.effective.0, 0);
+    }
+}
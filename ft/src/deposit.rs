@@ -0,0 +1,150 @@
+//! Deposit estimation for calls that grow the contract's storage.
+use near_contract_standards::storage_management::StorageManagement;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{Promise, StorageUsage};
+
+use crate::*;
+
+/// A state-growing operation together with the parameters that determine its storage cost.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde", tag = "type")]
+pub enum OperationSpec {
+    /// Registering `account_id`, either through `storage_deposit` or an owner mint to a new
+    /// receiver.
+    Register { account_id: AccountId },
+    /// `create_proposal` by `proposer` with `description`.
+    CreateProposal { proposer: AccountId, description: String },
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the deposit in yoctoNEAR that must be attached for `operation` to succeed given the
+    /// current state. Zero means the operation does not need a deposit.
+    pub fn required_deposit(&self, operation: OperationSpec) -> U128 {
+        match operation {
+            OperationSpec::Register { account_id } => {
+                if self.token.accounts.contains_key(&account_id) {
+                    U128(0)
+                } else {
+                    self.token.storage_balance_bounds().min
+                }
+            }
+            OperationSpec::CreateProposal { proposer, description } => U128(
+                Balance::from(Self::proposal_storage_usage(proposer, description))
+                    * env::storage_byte_cost(),
+            ),
+        }
+    }
+}
+
+impl Contract {
+    /// Charges the caller for the storage the call added since `initial_storage_usage` out of the
+    /// attached deposit, and refunds the rest. At least 1 yoctoNEAR is always kept, so these calls
+    /// also require a full access key; storage the call frees is not refunded.
+    pub(crate) fn charge_storage(initial_storage_usage: StorageUsage) {
+        Self::charge_storage_reserving(initial_storage_usage, 0);
+    }
+
+    /// Like `charge_storage`, also charging for `reserved` bytes the contract will use on the
+    /// caller's behalf later.
+    pub(crate) fn charge_storage_reserving(
+        initial_storage_usage: StorageUsage,
+        reserved: StorageUsage,
+    ) {
+        let attached_deposit = env::attached_deposit();
+        assert!(attached_deposit > 0, "Requires attached deposit of at least 1 yoctoNEAR");
+        let storage_cost =
+            Balance::from(env::storage_usage().saturating_sub(initial_storage_usage) + reserved)
+                * env::storage_byte_cost();
+        assert!(
+            attached_deposit >= storage_cost,
+            "The attached deposit is less than the storage cost of {}",
+            storage_cost
+        );
+        let kept = storage_cost.max(1);
+        if attached_deposit > kept {
+            Promise::new(env::predecessor_account_id()).transfer(attached_deposit - kept);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, TOTAL_SUPPLY};
+    use crate::*;
+
+    #[test]
+    fn test_register_deposit_matches_storage_deposit() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        let operation = OperationSpec::Register { account_id: accounts(2) };
+        let required = contract.required_deposit(operation.clone());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(required.0)
+            .predecessor_account_id(accounts(2))
+            .build());
+        let storage_balance = contract.storage_deposit(None, None);
+        assert_eq!(storage_balance.total, required);
+        assert_eq!(contract.required_deposit(operation), U128(0));
+    }
+
+    #[test]
+    fn test_register_deposit_matches_mint() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        let required =
+            contract.required_deposit(OperationSpec::Register { account_id: accounts(3) });
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(required.0)
+            .build());
+        contract.ft_mint(accounts(3), U128(1), None);
+        assert_eq!(contract.storage_balance_of(accounts(3)).unwrap().total, required);
+    }
+
+    #[test]
+    fn test_proposal_deposit_matches_storage_cost() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        let description = "Fund the grants program".to_string();
+        let required = contract.required_deposit(OperationSpec::CreateProposal {
+            proposer: accounts(1),
+            description: description.clone(),
+        });
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(required.0)
+            .build());
+        contract.create_proposal(description, 100, None);
+        assert!(get_created_receipts().is_empty(), "The exact deposit leaves nothing to refund");
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_proposal_deposit_is_not_overestimated() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        let description = "Fund the grants program".to_string();
+        let required = contract.required_deposit(OperationSpec::CreateProposal {
+            proposer: accounts(1),
+            description: description.clone(),
+        });
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(required.0 - 1)
+            .build());
+        contract.create_proposal(description, 100, None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Earmarks: labeled buckets within an account's own balance, e.g. a treasury's budget lines.
+//! Earmarked tokens stay in the account, so `ft_balance_of` is unchanged, but they can only leave
+//! it through `spend_earmark`. The free balance is what remains outside the earmarks, so the
+//! earmarks plus the free balance always add up to the account's balance.
+use near_sdk::assert_one_yocto;
+
+use crate::*;
+
+/// Caps the earmarks of one account so listing and summing them stays cheap.
+pub const MAX_EARMARKS_PER_ACCOUNT: usize = 20;
+pub const MAX_EARMARK_LABEL_LEN: usize = 64;
+
+#[near_bindgen]
+impl Contract {
+    /// Moves `amount` of the caller's free balance into the earmark `label`, creating it if
+    /// needed. Requires a deposit covering the storage of the earmark; any excess is refunded.
+    #[payable]
+    pub fn create_earmark(&mut self, label: String, amount: U128) {
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        self.assert_registered(&account_id);
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        assert!(
+            !label.is_empty() && label.len() <= MAX_EARMARK_LABEL_LEN,
+            "The label should be 1 to {} bytes long",
+            MAX_EARMARK_LABEL_LEN
+        );
+        self.assert_free_balance(&account_id, amount.0);
+        let mut earmarks = self.earmarks.get(&account_id).unwrap_or_default();
+        match earmarks.iter_mut().find(|(existing, _)| *existing == label) {
+            Some((_, earmarked)) => *earmarked += amount.0,
+            None => {
+                assert!(
+                    earmarks.len() < MAX_EARMARKS_PER_ACCOUNT,
+                    "The account {} has too many earmarks",
+                    account_id
+                );
+                earmarks.push((label.clone(), amount.0));
+            }
+        }
+        self.earmarks.insert(&account_id, &earmarks);
+        log!("@{} earmarked {} for {}", account_id, amount.0, label);
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Transfers `amount` out of the caller's earmark `label` to `to`, less the transfer fee.
+    /// Requires 1 yoctoNEAR.
+    #[payable]
+    pub fn spend_earmark(&mut self, label: String, to: AccountId, amount: U128) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert!(account_id != to, "Sender and receiver should be different");
+        self.assert_registered(&to);
+        self.take_from_earmark(&account_id, &label, amount.0);
+        self.assert_transfer(&account_id, &to, amount.0);
+        let memo = Some(format!("Earmark {}", label));
+        let received = self.internal_transfer_with_fee(&account_id, &to, amount.0, memo.clone());
+        self.record_receipt(&account_id, &to, received, memo);
+    }
+
+    /// Returns `amount` of the caller's earmark `label` to its free balance.
+    pub fn release_earmark(&mut self, label: String, amount: U128) {
+        let account_id = env::predecessor_account_id();
+        self.take_from_earmark(&account_id, &label, amount.0);
+        log!("@{} released {} from {}", account_id, amount.0, label);
+    }
+
+    pub fn earmarks_of(&self, account_id: AccountId) -> Vec<(String, U128)> {
+        self.earmarks
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(label, amount)| (label, amount.into()))
+            .collect()
+    }
+
+    /// Returns the balance of `account_id` outside its earmarks.
+    pub fn free_balance_of(&self, account_id: AccountId) -> U128 {
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        balance.saturating_sub(self.earmarked_balance(&account_id)).into()
+    }
+}
+
+impl Contract {
+    /// Checks that `amount` fits in the free part of `balance`, the balance of `account_id`.
+    pub(crate) fn check_free_balance(
+        &self,
+        account_id: &AccountId,
+        balance: Balance,
+        amount: Balance,
+    ) -> Result<(), String> {
+        let earmarked = self.earmarked_balance(account_id);
+        let free = balance.saturating_sub(earmarked);
+        if earmarked > 0 && free < amount {
+            return Err(format!(
+                "The account {} doesn't have enough balance outside its earmarks",
+                account_id
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn assert_free_balance(&self, account_id: &AccountId, amount: Balance) {
+        let balance = self.token.accounts.get(account_id).unwrap_or(0);
+        if let Err(message) = self.check_free_balance(account_id, balance, amount) {
+            panic!("{}", message);
+        }
+        assert!(balance >= amount, "The account doesn't have enough balance");
+    }
+
+    fn earmarked_balance(&self, account_id: &AccountId) -> Balance {
+        self.earmarks
+            .get(account_id)
+            .map_or(0, |earmarks| earmarks.iter().map(|(_, amount)| amount).sum())
+    }
+
+    fn take_from_earmark(&mut self, account_id: &AccountId, label: &str, amount: Balance) {
+        assert!(amount > 0, "The amount should be a positive number");
+        let mut earmarks = self.earmarks.get(account_id).unwrap_or_default();
+        let index = earmarks
+            .iter()
+            .position(|(existing, _)| existing == label)
+            .unwrap_or_else(|| panic!("No earmark {}", label));
+        assert!(earmarks[index].1 >= amount, "Not enough earmarked for {}", label);
+        earmarks[index].1 -= amount;
+        if earmarks[index].1 == 0 {
+            earmarks.remove(index);
+        }
+        if earmarks.is_empty() {
+            self.earmarks.remove(account_id);
+        } else {
+            self.earmarks.insert(account_id, &earmarks);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    /// Bob earmarks 300 for grants and 200 for audits.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.create_earmark("grants".to_string(), U128(300));
+        contract.create_earmark("audits".to_string(), U128(200));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract
+    }
+
+    fn assert_invariant(contract: &Contract, account_id: AccountId) {
+        let earmarked: Balance =
+            contract.earmarks_of(account_id.clone()).iter().map(|(_, amount)| amount.0).sum();
+        assert_eq!(
+            earmarked + contract.free_balance_of(account_id.clone()).0,
+            contract.ft_balance_of(account_id).0
+        );
+    }
+
+    #[test]
+    fn test_spend_earmark() {
+        let mut contract = setup();
+        assert_eq!(contract.free_balance_of(accounts(1)).0, TOTAL_SUPPLY - 500);
+        assert_invariant(&contract, accounts(1));
+
+        contract.spend_earmark("grants".to_string(), accounts(2), U128(120));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 120);
+        assert_eq!(
+            contract.earmarks_of(accounts(1)),
+            vec![("grants".to_string(), U128(180)), ("audits".to_string(), U128(200))]
+        );
+        assert_eq!(contract.free_balance_of(accounts(1)).0, TOTAL_SUPPLY - 500);
+        assert_invariant(&contract, accounts(1));
+
+        contract.release_earmark("audits".to_string(), U128(200));
+        assert_eq!(contract.earmarks_of(accounts(1)), vec![("grants".to_string(), U128(180))]);
+        assert_invariant(&contract, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob doesn't have enough balance outside its earmarks")]
+    fn test_transfer_of_earmarked_tokens() {
+        let mut contract = setup();
+        contract.ft_transfer(accounts(2), U128(TOTAL_SUPPLY - 499), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_create_earmark_requires_storage_deposit() {
+        let mut contract = setup();
+        contract.create_earmark("payroll".to_string(), U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough earmarked for audits")]
+    fn test_overspend_earmark() {
+        let mut contract = setup();
+        contract.spend_earmark("audits".to_string(), accounts(2), U128(201));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob doesn't have enough balance outside its earmarks")]
+    fn test_batch_of_earmarked_tokens() {
+        let mut contract = setup();
+        let free = contract.free_balance_of(accounts(1)).0;
+        contract.ft_transfer_batch(
+            vec![(accounts(2), U128(free - 10)), (accounts(2), U128(10)), (accounts(2), U128(10))],
+            None,
+        );
+    }
+
+    #[test]
+    fn test_simulated_transfers_of_earmarked_tokens() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), 1_000.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.create_earmark("grants".to_string(), U128(500));
+        let op = (accounts(1), accounts(2), U128(400));
+        assert_eq!(
+            contract.simulate_transfers(vec![op.clone(), op]),
+            vec![
+                Ok(U128(400)),
+                Err("The account bob doesn't have enough balance outside its earmarks".to_string()),
+            ]
+        );
+    }
+}
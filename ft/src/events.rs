@@ -0,0 +1,103 @@
+//! Contract-specific events in the NEP-297 format, under the `adashi` standard. They mirror the
+//! NEP-141 events of near-contract-standards, so indexers can parse both the same way:
+//!
+//! `EVENT_JSON:{"standard":"adashi","version":"1.0.0","event":"account_closed","data":[...]}`
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json;
+
+use crate::*;
+
+/// Data to log when a storage unregistration closes an account, burning its balance.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountClosed<'a> {
+    pub account_id: &'a AccountId,
+    pub balance: &'a U128,
+}
+
+impl AccountClosed<'_> {
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    pub fn emit_many(data: &[AccountClosed<'_>]) {
+        AdashiEvent::new(AdashiEventKind::AccountClosed(data)).emit()
+    }
+}
+
+/// Data to log when tokens leave the total supply, including the ones a receiver burned
+/// through `ft_transfer_call` by unregistering before the refund.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokensBurned<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: &'a U128,
+}
+
+impl TokensBurned<'_> {
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    pub fn emit_many(data: &[TokensBurned<'_>]) {
+        AdashiEvent::new(AdashiEventKind::TokensBurned(data)).emit()
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+struct AdashiEvent<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: AdashiEventKind<'a>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+enum AdashiEventKind<'a> {
+    AccountClosed(&'a [AccountClosed<'a>]),
+    TokensBurned(&'a [TokensBurned<'a>]),
+}
+
+impl<'a> AdashiEvent<'a> {
+    fn new(event_kind: AdashiEventKind<'a>) -> Self {
+        Self { standard: "adashi", version: "1.0.0", event_kind }
+    }
+
+    fn emit(self) {
+        let json = serde_json::to_string(&self).unwrap_or_else(|_| env::abort());
+        env::log_str(&format!("EVENT_JSON:{}", json));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    #[test]
+    fn test_account_closed_event() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), U128(10), None);
+
+        testing_env!(get_context(accounts(2)).attached_deposit(1).build());
+        assert!(contract.storage_unregister(Some(true)));
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"adashi","version":"1.0.0","event":"account_closed","data":[{"account_id":"charlie","balance":"10"}]}"#
+        );
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 10);
+    }
+}
@@ -0,0 +1,253 @@
+//! Optional fee on transfers. The receiver gets the amount less `fee_basis_points` of it:
+//! `burn_share_bps` of the amount is burned and the rest of the fee goes to `fee_treasury`. Both
+//! rates are in basis points of the transferred amount and round down.
+//!
+//! Every transfer a holder initiates pays it: `ft_transfer`, `ft_transfer_call`,
+//! `ft_transfer_batch` (per transfer), `ft_transfer_from`, `spend_earmark` and
+//! `transfer_with_lock`. `ft_permit` only sets an allowance, so the fee is paid when the spender
+//! uses it. Movements out of the contract's custody (vesting, lock releases, unstaking, rewards
+//! and airdrops) and minting are exempt, as are the fee payments themselves.
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::staking::uint256::U256;
+use crate::*;
+
+const MAX_BASIS_POINTS: u16 = 10_000;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferFee {
+    pub fee_basis_points: u16,
+    pub burn_share_bps: u16,
+    pub treasury: AccountId,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the transfer fee and the part of it that is burned, both in basis points of the
+    /// transferred amount. The treasury must be registered. A zero fee disables it.
+    pub fn set_transfer_fee(&mut self, fee_bps: u16, burn_share_bps: u16, treasury: AccountId) {
+        self.assert_owner();
+        assert!(
+            fee_bps <= MAX_BASIS_POINTS,
+            "The fee can't exceed {} basis points",
+            MAX_BASIS_POINTS
+        );
+        assert!(burn_share_bps <= fee_bps, "The burn share can't exceed the fee");
+        self.assert_registered(&treasury);
+        self.fee_basis_points = fee_bps;
+        self.burn_share_bps = burn_share_bps;
+        self.fee_treasury = treasury;
+        log!(
+            "Transfer fee set to {} basis points, {} of them burned and the rest sent to @{}",
+            fee_bps,
+            burn_share_bps,
+            self.fee_treasury
+        );
+    }
+
+    pub fn get_transfer_fee(&self) -> TransferFee {
+        TransferFee {
+            fee_basis_points: self.fee_basis_points,
+            burn_share_bps: self.burn_share_bps,
+            treasury: self.fee_treasury.clone(),
+        }
+    }
+}
+
+impl Contract {
+    /// Returns the parts of the fee on a transfer of `amount` that are burned and sent to the
+    /// treasury.
+    pub(crate) fn transfer_fee(&self, amount: Balance) -> (Balance, Balance) {
+        let share = |bps: u16| {
+            (U256::from(amount) * U256::from(bps) / U256::from(MAX_BASIS_POINTS)).as_u128()
+        };
+        let burned = share(self.burn_share_bps);
+        (burned, share(self.fee_basis_points) - burned)
+    }
+
+    /// Returns what the receiver of a transfer of `amount` gets once the fee is deducted.
+    pub(crate) fn amount_after_fee(&self, amount: Balance) -> Balance {
+        let (burned, to_treasury) = self.transfer_fee(amount);
+        let fee = burned + to_treasury;
+        assert!(fee == 0 || amount > fee, "The amount doesn't cover the transfer fee");
+        amount - burned - to_treasury
+    }
+
+    /// Transfers `amount` from `sender_id`, of which `receiver_id` gets the part left after the
+    /// fee, and charges the fee. Returns the received amount.
+    pub(crate) fn internal_transfer_with_fee(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) -> Balance {
+        let received = self.amount_after_fee(amount);
+        self.assert_free_balance(sender_id, amount);
+        self.internal_transfer(sender_id, receiver_id, received, memo);
+        self.charge_transfer_fee(sender_id, amount);
+        received
+    }
+
+    /// Charges `sender_id` the fee on a transfer of `amount`. A treasury paying the fee to itself
+    /// only burns the burn share.
+    pub(crate) fn charge_transfer_fee(&mut self, sender_id: &AccountId, amount: Balance) {
+        let (burned, to_treasury) = self.transfer_fee(amount);
+        let treasury = self.fee_treasury.clone();
+        if to_treasury > 0 && *sender_id != treasury {
+            self.internal_transfer(
+                sender_id,
+                &treasury,
+                to_treasury,
+                Some("Transfer fee".to_string()),
+            );
+        }
+        if burned > 0 {
+            self.internal_burn(sender_id, burned, Some("Transfer fee"));
+        }
+        if burned + to_treasury > 0 {
+            log!(
+                "@{} paid a transfer fee of {}: {} burned and {} sent to @{}",
+                sender_id,
+                burned + to_treasury,
+                burned,
+                to_treasury,
+                treasury
+            );
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    /// Bob charges a 5% fee, 2% of it burned and the rest sent to danny, and sends charlie 1_000.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        register(&mut contract, accounts(3));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), U128(1_000), None);
+        contract.set_transfer_fee(500, 200, accounts(3));
+        contract
+    }
+
+    #[test]
+    fn test_transfer_fee_is_split() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer(accounts(1), U128(200), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 800);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000 + 190);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 6);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 4);
+        assert_eq!(
+            contract.get_transfer_fee(),
+            TransferFee { fee_basis_points: 500, burn_share_bps: 200, treasury: accounts(3) }
+        );
+    }
+
+    /// Charlie sends 200 of her 1_000 to bob through some path: bob gets 190, danny 6 and 4 burn.
+    fn assert_fee_paid(contract: &Contract) {
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 800);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 6);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 4);
+    }
+
+    fn as_charlie(attached_deposit: Balance) {
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(attached_deposit)
+            .build());
+    }
+
+    #[test]
+    fn test_fee_on_transfer_call() {
+        let mut contract = setup();
+        as_charlie(1);
+        contract.ft_transfer_call(accounts(1), U128(200), None, String::new());
+        assert_fee_paid(&contract);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000 + 190);
+    }
+
+    #[test]
+    fn test_fee_on_batch() {
+        let mut contract = setup();
+        as_charlie(1);
+        contract.ft_transfer_batch(vec![(accounts(1), U128(100)), (accounts(1), U128(100))], None);
+        assert_fee_paid(&contract);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000 + 190);
+    }
+
+    #[test]
+    fn test_fee_on_transfer_from() {
+        let mut contract = setup();
+        as_charlie(ONE_NEAR);
+        contract.ft_approve(accounts(4), U128(200));
+        testing_env!(get_context(accounts(4)).attached_deposit(1).build());
+        contract.ft_transfer_from(accounts(2), accounts(1), U128(200), None);
+        assert_fee_paid(&contract);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000 + 190);
+        assert_eq!(contract.ft_allowance(accounts(2), accounts(4)).0, 0);
+    }
+
+    #[test]
+    fn test_fee_on_spend_earmark() {
+        let mut contract = setup();
+        as_charlie(ONE_NEAR);
+        contract.create_earmark("grants".to_string(), U128(200));
+        as_charlie(1);
+        contract.spend_earmark("grants".to_string(), accounts(1), U128(200));
+        assert_fee_paid(&contract);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000 + 190);
+    }
+
+    #[test]
+    fn test_fee_on_transfer_with_lock() {
+        let mut contract = setup();
+        contract.set_min_lock_amount(U128(1));
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.grant_receive_consent(accounts(2));
+        as_charlie(ONE_NEAR);
+        contract.transfer_with_lock(accounts(1), U128(200), 1);
+        assert_fee_paid(&contract);
+        assert_eq!(contract.locked_balance_of(accounts(1)).0, 190);
+    }
+
+    #[test]
+    fn test_fee_does_not_overflow() {
+        let contract = setup();
+        assert_eq!(
+            contract.transfer_fee(Balance::MAX),
+            (Balance::MAX / 50, Balance::MAX / 20 - Balance::MAX / 50)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The fee can't exceed 10000 basis points")]
+    fn test_fee_above_the_amount() {
+        let mut contract = setup();
+        contract.set_transfer_fee(10_001, 0, accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "The burn share can't exceed the fee")]
+    fn test_burn_share_above_the_fee() {
+        let mut contract = setup();
+        contract.set_transfer_fee(100, 101, accounts(3));
+    }
+}
@@ -0,0 +1,537 @@
+//! Token-weighted governance proposals. Votes are weighted by the voter's balance at the block
+//! before the proposal was created, so tokens moved after creation cannot vote twice. Delegation,
+//! and with it the minimum delegation age, only affects `get_votes`: `vote` always weighs the
+//! voter's own balance, so there is no recent delegation to leave out.
+//!
+//! Each account's voting history keeps only its latest `VOTING_HISTORY_CAPACITY` votes,
+//! overwriting the oldest one once full, so the storage the history takes is bounded. The
+//! proposals themselves keep the full tallies.
+use near_sdk::collections::Vector;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::BlockHeight;
+
+use crate::*;
+
+pub const VOTING_HISTORY_CAPACITY: u64 = 100;
+
+/// How a voter's snapshot balance turns into votes.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum VotingMode {
+    /// One vote per token.
+    Linear,
+    /// The integer square root of the balance, rounded down, damping the weight of large
+    /// holders.
+    Quadratic,
+}
+
+impl VotingMode {
+    fn weight(self, balance: Balance) -> Balance {
+        match self {
+            VotingMode::Linear => balance,
+            VotingMode::Quadratic => isqrt(balance),
+        }
+    }
+}
+
+/// The integer square root of `n`, rounded down, by Newton's method.
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    // A power of two at or above the root, so the iterates only decrease and `x + n / x` stays
+    // below 2^66.
+    let mut x: u128 = 1 << ((128 - n.leading_zeros()).div_ceil(2));
+    loop {
+        let next = (x + n / x) / 2;
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
+
+/// Times are block timestamps in nanoseconds.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: AccountId,
+    pub description: String,
+    #[serde(with = "crate::u128_dec_format")]
+    pub votes_for: Balance,
+    #[serde(with = "crate::u128_dec_format")]
+    pub votes_against: Balance,
+    pub start: u64,
+    pub end: u64,
+    /// Block whose final balances weight the votes.
+    pub snapshot_block: BlockHeight,
+    pub voting_mode: VotingMode,
+    pub executed: bool,
+}
+
+impl Proposal {
+    fn new(
+        id: u64,
+        proposer: AccountId,
+        description: String,
+        voting_mode: VotingMode,
+        voting_period_seconds: u64,
+    ) -> Self {
+        let start = env::block_timestamp();
+        Self {
+            id,
+            proposer,
+            description,
+            votes_for: 0,
+            votes_against: 0,
+            start,
+            end: voting_period_seconds
+                .checked_mul(1_000_000_000)
+                .and_then(|voting_period| start.checked_add(voting_period))
+                .expect("The voting period is too long"),
+            snapshot_block: env::block_height().saturating_sub(1),
+            voting_mode,
+            executed: false,
+        }
+    }
+}
+
+/// Ring buffer of an account's latest `(proposal id, support, weight)` votes.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VotingHistory {
+    entries: Vector<(u64, bool, Balance)>,
+    /// Number of votes ever appended. The next one goes to `total % VOTING_HISTORY_CAPACITY`.
+    total: u64,
+}
+
+impl VotingHistory {
+    /// Index into `entries` of the oldest retained vote.
+    fn start(&self) -> u64 {
+        if self.total > VOTING_HISTORY_CAPACITY {
+            self.total % VOTING_HISTORY_CAPACITY
+        } else {
+            0
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Opens a proposal for voting during the next `voting_period_seconds`. The caller must hold
+    /// at least `proposal_threshold` and attach a deposit covering the proposal's storage, see
+    /// `required_deposit`. Any excess is refunded. Votes are weighted with `voting_mode`,
+    /// `Linear` by default. Returns the proposal id.
+    ///
+    /// Proposals with an empty or whitespace-only description are rejected as spam.
+    #[payable]
+    pub fn create_proposal(
+        &mut self,
+        description: String,
+        voting_period_seconds: u64,
+        voting_mode: Option<VotingMode>,
+    ) -> u64 {
+        let initial_storage_usage = env::storage_usage();
+        let id = self.internal_create_proposal(description, voting_period_seconds, voting_mode);
+        Self::charge_storage(initial_storage_usage);
+        id
+    }
+
+    /// Creates a proposal like `create_proposal` and casts the proposer's vote on it in the same
+    /// call, weighted from the same snapshot. The deposit covers the storage of both. Returns the
+    /// proposal id.
+    #[payable]
+    pub fn create_proposal_and_vote(
+        &mut self,
+        description: String,
+        voting_period_seconds: u64,
+        voting_mode: Option<VotingMode>,
+        support: bool,
+    ) -> u64 {
+        let initial_storage_usage = env::storage_usage();
+        let id = self.internal_create_proposal(description, voting_period_seconds, voting_mode);
+        self.internal_vote(id, support);
+        Self::charge_storage(initial_storage_usage);
+        id
+    }
+
+    /// Votes on an open proposal with the caller's balance at the proposal's snapshot block,
+    /// weighted with the proposal's voting mode. Requires a deposit covering the storage of the
+    /// vote and its entry in the voter's history; any excess is refunded.
+    #[payable]
+    pub fn vote(&mut self, proposal_id: u64, support: bool) {
+        let initial_storage_usage = env::storage_usage();
+        self.internal_vote(proposal_id, support);
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Returns up to `limit` of the retained votes of `account_id` as `(proposal id, support,
+    /// weight)`, oldest first, skipping the first `from_index`.
+    pub fn voting_history_of(
+        &self,
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(u64, bool, U128)> {
+        let history = match self.voting_history.get(&account_id) {
+            Some(history) => history,
+            None => return vec![],
+        };
+        let len = history.entries.len();
+        (from_index..len.min(from_index.saturating_add(limit)))
+            .map(|i| {
+                let (proposal_id, support, weight) =
+                    history.entries.get((history.start() + i) % len).unwrap();
+                (proposal_id, support, weight.into())
+            })
+            .collect()
+    }
+
+    pub fn get_proposal(&self, id: u64) -> Option<Proposal> {
+        self.proposals.get(id)
+    }
+
+    pub fn set_proposal_threshold(&mut self, proposal_threshold: U128) {
+        self.assert_owner();
+        self.proposal_threshold = proposal_threshold.into();
+    }
+
+    pub fn proposal_threshold(&self) -> U128 {
+        self.proposal_threshold.into()
+    }
+}
+
+impl Contract {
+    fn internal_create_proposal(
+        &mut self,
+        description: String,
+        voting_period_seconds: u64,
+        voting_mode: Option<VotingMode>,
+    ) -> u64 {
+        assert!(!description.trim().is_empty(), "The proposal description should not be empty");
+        let proposer = env::predecessor_account_id();
+        self.assert_registered(&proposer);
+        assert!(
+            self.token.accounts.get(&proposer).unwrap() >= self.proposal_threshold,
+            "Creating a proposal requires a balance of at least {}",
+            self.proposal_threshold
+        );
+        assert!(voting_period_seconds > 0, "The voting period should be positive");
+
+        let id = self.proposals.len();
+        let proposal = Proposal::new(
+            id,
+            proposer.clone(),
+            description,
+            voting_mode.unwrap_or(VotingMode::Linear),
+            voting_period_seconds,
+        );
+        self.last_proposal_snapshot = Some(proposal.snapshot_block);
+        self.proposals.push(&proposal);
+        log!("@{} created proposal {}", proposer, id);
+        id
+    }
+
+    fn internal_vote(&mut self, proposal_id: u64, support: bool) {
+        let voter = env::predecessor_account_id();
+        let mut proposal = self.proposals.get(proposal_id).expect("No such proposal");
+        assert!(env::block_timestamp() <= proposal.end, "Voting has ended");
+        assert!(
+            self.voted.insert(&(proposal_id, voter.clone())),
+            "The account {} already voted on proposal {}",
+            voter,
+            proposal_id
+        );
+        let weight = proposal
+            .voting_mode
+            .weight(self.ft_balance_of_at(voter.clone(), proposal.snapshot_block).0);
+        assert!(weight > 0, "The account {} had no balance at the proposal snapshot", voter);
+        if support {
+            proposal.votes_for += weight;
+        } else {
+            proposal.votes_against += weight;
+        }
+        self.proposals.replace(proposal_id, &proposal);
+        self.record_vote(&voter, proposal_id, support, weight);
+        log!(
+            "@{} voted {} proposal {} with {}",
+            voter,
+            if support { "for" } else { "against" },
+            proposal_id,
+            weight
+        );
+    }
+
+    fn record_vote(&mut self, voter: &AccountId, proposal_id: u64, support: bool, weight: Balance) {
+        let mut history = self.voting_history.get(voter).unwrap_or_else(|| VotingHistory {
+            entries: Vector::new([b"V".as_ref(), &env::sha256(voter.as_bytes())].concat()),
+            total: 0,
+        });
+        let vote = (proposal_id, support, weight);
+        if history.entries.len() < VOTING_HISTORY_CAPACITY {
+            history.entries.push(&vote);
+        } else {
+            history.entries.replace(history.total % VOTING_HISTORY_CAPACITY, &vote);
+        }
+        history.total += 1;
+        self.voting_history.insert(voter, &history);
+    }
+
+    /// Storage a new proposal by `proposer` with `description` adds: the element record of
+    /// `proposals`, i.e. its key, its value and the runtime's per-record overhead.
+    pub(crate) fn proposal_storage_usage(proposer: AccountId, description: String) -> u64 {
+        const KEY_LEN: u64 = 1 + 8;
+        const RECORD_OVERHEAD: u64 = 40;
+        let value_len = Proposal::new(0, proposer, description, VotingMode::Linear, 0)
+            .try_to_vec()
+            .unwrap()
+            .len();
+        KEY_LEN + value_len as u64 + RECORD_OVERHEAD
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use super::isqrt;
+    use crate::tests::{delegate_as, get_context, register, ONE_NEAR};
+    use crate::*;
+
+    /// Bob holds 900 and charlie 100 at block 10; bob opens a 100 second proposal at block 11.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_index(10).build());
+        let mut contract = Contract::new_default_meta(accounts(1), 1_000.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_index(10)
+            .build());
+        contract.ft_transfer(accounts(2), U128(100), None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(11)
+            .build());
+        assert_eq!(contract.create_proposal("Fund the grants program".to_string(), 100, None), 0);
+        contract
+    }
+
+    fn vote_as(contract: &mut Contract, voter: AccountId, support: bool) {
+        testing_env!(get_context(voter)
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(12)
+            .build());
+        contract.vote(0, support);
+    }
+
+    #[test]
+    fn test_vote_weighted_by_snapshot() {
+        let mut contract = setup();
+        // Tokens moved after the snapshot don't add to the receiver's weight.
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_index(12)
+            .build());
+        contract.ft_transfer(accounts(2), U128(800), None);
+
+        vote_as(&mut contract, accounts(1), true);
+        vote_as(&mut contract, accounts(2), false);
+        let proposal = contract.get_proposal(0).unwrap();
+        assert_eq!(proposal.proposer, accounts(1));
+        assert_eq!(proposal.votes_for, 900);
+        assert_eq!(proposal.votes_against, 100);
+        assert!(!proposal.executed);
+    }
+
+    #[test]
+    fn test_delegation_does_not_move_vote_weight() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1)).block_index(12).build());
+        contract.set_min_delegation_age(1_000);
+        delegate_as(&mut contract, accounts(1), accounts(2));
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(13)
+            .build());
+        let id = contract.create_proposal("Extend the grants program".to_string(), 100, None);
+        for voter in [accounts(1), accounts(2)] {
+            testing_env!(get_context(voter)
+                .storage_usage(env::storage_usage())
+                .attached_deposit(ONE_NEAR)
+                .block_index(14)
+                .block_timestamp(1_000_000_000)
+                .build());
+            contract.vote(id, true);
+        }
+        assert_eq!(contract.get_votes(accounts(2)).0, 900);
+        assert_eq!(contract.voting_history_of(accounts(1), 0, 1), vec![(id, true, U128(900))]);
+        assert_eq!(contract.voting_history_of(accounts(2), 0, 1), vec![(id, true, U128(100))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account danny had no balance at the proposal snapshot")]
+    fn test_vote_without_snapshot_balance() {
+        let mut contract = setup();
+        register(&mut contract, accounts(3));
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_index(12)
+            .build());
+        contract.ft_transfer(accounts(3), U128(100), None);
+        vote_as(&mut contract, accounts(3), true);
+    }
+
+    #[test]
+    #[should_panic(expected = "The voting period is too long")]
+    fn test_create_proposal_with_overflowing_period() {
+        let mut contract = setup();
+        contract.create_proposal("Never close".to_string(), u64::MAX, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account charlie already voted on proposal 0")]
+    fn test_double_vote() {
+        let mut contract = setup();
+        vote_as(&mut contract, accounts(2), true);
+        vote_as(&mut contract, accounts(2), true);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_vote_without_storage_deposit() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_index(12)
+            .build());
+        contract.vote(0, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting has ended")]
+    fn test_vote_after_deadline() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2)).block_timestamp(100_000_000_001).build());
+        contract.vote(0, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Creating a proposal requires a balance of at least 500")]
+    fn test_create_proposal_below_threshold() {
+        let mut contract = setup();
+        contract.set_proposal_threshold(U128(500));
+        testing_env!(get_context(accounts(2)).attached_deposit(ONE_NEAR).build());
+        contract.create_proposal("Lower the threshold".to_string(), 100, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The proposal description should not be empty")]
+    fn test_create_proposal_without_description() {
+        let mut contract = setup();
+        contract.create_proposal(" \n".to_string(), 100, None);
+    }
+
+    #[test]
+    fn test_voting_history() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(11)
+            .build());
+        assert_eq!(contract.create_proposal("Extend the grants program".to_string(), 100, None), 1);
+        assert_eq!(contract.create_proposal("Close the grants program".to_string(), 100, None), 2);
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.vote(2, false);
+        contract.vote(0, true);
+        contract.vote(1, true);
+
+        assert_eq!(
+            contract.voting_history_of(accounts(2), 0, 10),
+            vec![(2, false, U128(100)), (0, true, U128(100)), (1, true, U128(100))]
+        );
+        assert_eq!(contract.voting_history_of(accounts(2), 1, 1), vec![(0, true, U128(100))]);
+        assert!(contract.voting_history_of(accounts(2), 3, 10).is_empty());
+        assert!(contract.voting_history_of(accounts(1), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_quadratic_voting() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(11)
+            .build());
+        let id = contract.create_proposal(
+            "Weigh votes quadratically".to_string(),
+            100,
+            Some(VotingMode::Quadratic),
+        );
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.vote(id, true);
+        let proposal = contract.get_proposal(id).unwrap();
+        assert_eq!(proposal.voting_mode, VotingMode::Quadratic);
+        assert_eq!(proposal.votes_for, 10);
+        assert_eq!(contract.get_proposal(0).unwrap().voting_mode, VotingMode::Linear);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for (n, root) in [(0, 0), (1, 1), (3, 1), (4, 2), (99, 9), (100, 10), (u128::MAX, u64::MAX)]
+        {
+            assert_eq!(isqrt(n), root as u128);
+        }
+        assert_eq!(isqrt(u128::from(u64::MAX) * u128::from(u64::MAX)), u128::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_create_proposal_and_vote() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(11)
+            .build());
+        let id = contract.create_proposal_and_vote(
+            "Renew the audit budget".to_string(),
+            100,
+            None,
+            false,
+        );
+        let proposal = contract.get_proposal(id).unwrap();
+        assert_eq!(proposal.proposer, accounts(1));
+        assert_eq!(proposal.votes_against, 900);
+        assert_eq!(contract.voting_history_of(accounts(1), 0, 10), vec![(id, false, U128(900))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob already voted on proposal 1")]
+    fn test_vote_again_after_create_proposal_and_vote() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(11)
+            .build());
+        contract.create_proposal_and_vote("Renew the audit budget".to_string(), 100, None, true);
+        contract.vote(1, true);
+    }
+}
@@ -0,0 +1,100 @@
+//! Enumeration of registered accounts. The token's own account map hashes its keys and cannot be
+//! iterated, so registrations are mirrored into `holders`, with `holder_index` pointing back into
+//! it so a closed account can be removed in constant time.
+use near_sdk::StorageUsage;
+
+use crate::*;
+
+/// Storage the holder bookkeeping adds per account, for the longest possible account id: the
+/// `holders` element (9 byte key, 68 byte value) and the `holder_index` entry (69 byte key,
+/// 8 byte value), plus the runtime's 40 byte overhead per record. Registrations pay for it on
+/// top of the account's balance record.
+pub(crate) const HOLDER_STORAGE_USAGE: StorageUsage = (9 + 68 + 40) + (69 + 8 + 40);
+
+#[near_bindgen]
+impl Contract {
+    /// Returns up to `limit` registered accounts with their balances, starting at `from_index`.
+    /// Every entry costs two storage reads, so keep `limit` to a few hundred per call.
+    ///
+    /// Closing an account moves the last holder into its slot, so pages fetched across a closure
+    /// can miss or repeat that one holder.
+    pub fn get_holders(&self, from_index: u64, limit: u64) -> Vec<(AccountId, U128)> {
+        (from_index..self.holders.len().min(from_index.saturating_add(limit)))
+            .map(|index| {
+                let account_id = self.holders.get(index).unwrap();
+                let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+                (account_id, balance.into())
+            })
+            .collect()
+    }
+
+    pub fn holder_count(&self) -> u64 {
+        self.holders.len()
+    }
+}
+
+impl Contract {
+    /// Registers `account_id` with the token and lists it as a holder.
+    pub(crate) fn internal_register_account(&mut self, account_id: &AccountId) {
+        self.token.internal_register_account(account_id);
+        self.add_holder(account_id);
+    }
+
+    pub(crate) fn add_holder(&mut self, account_id: &AccountId) {
+        self.holder_index.insert(account_id, &self.holders.len());
+        self.holders.push(account_id);
+    }
+
+    pub(crate) fn remove_holder(&mut self, account_id: &AccountId) {
+        if let Some(index) = self.holder_index.remove(account_id) {
+            self.holders.swap_remove(index);
+            if let Some(moved) = self.holders.get(index) {
+                self.holder_index.insert(&moved, &index);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        register(&mut contract, accounts(3));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(3), U128(7), None);
+        contract
+    }
+
+    #[test]
+    fn test_get_holders() {
+        let contract = setup();
+        assert_eq!(contract.holder_count(), 3);
+        assert_eq!(
+            contract.get_holders(0, 2),
+            vec![(accounts(1), U128(TOTAL_SUPPLY - 7)), (accounts(2), U128(0))]
+        );
+        assert_eq!(contract.get_holders(2, 10), vec![(accounts(3), U128(7))]);
+        assert!(contract.get_holders(3, 10).is_empty());
+    }
+
+    #[test]
+    fn test_closed_account_leaves_no_gap() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2)).attached_deposit(1).build());
+        assert!(contract.storage_unregister(None));
+        assert_eq!(contract.holder_count(), 2);
+        assert_eq!(
+            contract.get_holders(0, 10),
+            vec![(accounts(1), U128(TOTAL_SUPPLY - 7)), (accounts(3), U128(7))]
+        );
+    }
+}
@@ -15,20 +15,174 @@ NOTES:
   - To prevent the deployed contract from being modified or deleted, it should not have any access
     keys on its account.
 */
+use std::collections::HashMap;
+
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
 use near_contract_standards::fungible_token::metadata::{
     FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
 };
+use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
 use near_contract_standards::fungible_token::FungibleToken;
+use near_contract_standards::storage_management::{
+    StorageBalance, StorageBalanceBounds, StorageManagement,
+};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedSet, Vector};
 use near_sdk::json_types::U128;
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault, PromiseOrValue};
+use near_sdk::{
+    env, log, near_bindgen, AccountId, Balance, BlockHeight, PanicOnDefault, PromiseOrValue,
+};
+
+mod acknowledgement;
+mod airdrop;
+mod allowance;
+mod batch;
+mod blacklist;
+mod burn;
+mod consent;
+mod delegation;
+mod deposit;
+mod earmarks;
+mod events;
+mod fee;
+mod governance;
+mod holders;
+mod locks;
+mod mint;
+mod ownership;
+mod pause;
+mod permit;
+mod receipts;
+mod receive_limits;
+mod rescue;
+mod snapshot;
+mod sponsorship;
+mod staking;
+mod supply;
+mod vesting;
+
+pub use crate::delegation::VotingPowerBreakdown;
+pub use crate::deposit::OperationSpec;
+pub use crate::fee::TransferFee;
+pub use crate::governance::{Proposal, VotingMode};
+pub use crate::receipts::Receipt;
+pub use crate::receive_limits::ReceiveLimit;
+pub use crate::vesting::VestingSchedule;
+
+/// Serializes a `Balance` field of a JSON view as a decimal string, like `U128`.
+pub(crate) mod u128_dec_format {
+    use near_sdk::json_types::U128;
+    use near_sdk::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(num: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        U128(*num).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        U128::deserialize(deserializer).map(|num| num.0)
+    }
+}
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
+    owner_id: AccountId,
+    /// Account nominated by the owner to take over, until it accepts.
+    pending_owner: Option<AccountId>,
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
+    /// Hard ceiling on the total supply enforced by every mint path. `None` means uncapped.
+    max_supply: Option<Balance>,
+    /// Accounts allowed to mint besides the owner.
+    minters: UnorderedSet<AccountId>,
+    /// Set for good once the owner renounces minting.
+    minting_renounced: bool,
+    /// While set, all transfers are rejected.
+    paused: bool,
+    /// Number of decimals UIs should display. Never exceeds the on-chain `decimals`.
+    display_decimals: u8,
+    /// Accounts frozen for compliance reasons.
+    blacklist: LookupSet<AccountId>,
+    /// Receiver -> amount above which incoming transfers need the receiver's prior consent.
+    receive_consent_thresholds: LookupMap<AccountId, Balance>,
+    /// (receiver, sender) pairs allowed to exceed the receiver's consent threshold.
+    receive_consents: LookupSet<(AccountId, AccountId)>,
+    /// Account -> `(block_height, balance)` checkpoints, ordered by height. See `snapshot`.
+    snapshots: LookupMap<AccountId, Vector<(BlockHeight, Balance)>>,
+    /// `(block_height, total_supply)` checkpoints, ordered by height.
+    supply_snapshots: Vector<(BlockHeight, Balance)>,
+    /// Snapshot block of the latest proposal, below which checkpoints are kept.
+    last_proposal_snapshot: Option<BlockHeight>,
+    /// Accounts that keep on-chain receipts of their incoming transfers.
+    receipt_opt_ins: LookupSet<AccountId>,
+    receipts: LookupMap<AccountId, receipts::ReceiptLog>,
+    /// Delegator -> delegatee receiving its voting power.
+    delegates: LookupMap<AccountId, AccountId>,
+    /// Delegatee -> sum of the balances delegated to it.
+    voting_power: LookupMap<AccountId, Balance>,
+    /// How old a delegation must be before it counts toward votes. Zero disables the rule.
+    min_delegation_age_ms: u64,
+    /// Delegator -> block timestamp of its latest delegation, recorded while the rule is enabled.
+    delegated_at: LookupMap<AccountId, u64>,
+    /// Delegatee -> delegators whose delegation to it may still be younger than the minimum age.
+    recent_delegators: LookupMap<AccountId, Vec<AccountId>>,
+    /// Beneficiary -> schedule of the tokens locked for it on the contract's own account.
+    vesting: LookupMap<AccountId, VestingSchedule>,
+    /// Receiver -> `(unlock_timestamp, amount)` of its timelocked transfers, held in custody.
+    locked: LookupMap<AccountId, Vector<(u64, Balance)>>,
+    /// Smallest amount a timelocked transfer can lock.
+    min_lock_amount: Balance,
+    /// (owner, spender) -> amount the spender may still transfer out of the owner's balance.
+    allowances: LookupMap<(AccountId, AccountId), Balance>,
+    /// Minimum balance needed to create a proposal.
+    proposal_threshold: Balance,
+    proposals: Vector<Proposal>,
+    /// (proposal id, voter) pairs that already voted.
+    voted: LookupSet<(u64, AccountId)>,
+    /// Voter -> its latest votes.
+    voting_history: LookupMap<AccountId, governance::VotingHistory>,
+    /// Staker -> its stake, held in custody on the contract's own account.
+    stakes: LookupMap<AccountId, staking::Stake>,
+    total_staked: Balance,
+    /// Rewards funded per staked token so far, scaled by `staking::REWARD_PRECISION`.
+    reward_per_token: u128,
+    /// Funded rewards not claimed yet, held in custody.
+    reward_pool: Balance,
+    /// Registered accounts, in no particular order.
+    holders: Vector<AccountId>,
+    /// Holder -> its index in `holders`.
+    holder_index: LookupMap<AccountId, u64>,
+    /// Fee charged on transfers, in basis points of the amount.
+    fee_basis_points: u16,
+    /// Receives the part of the transfer fee that isn't burned.
+    fee_treasury: AccountId,
+    /// Part of the transfer fee that is burned, in basis points of the amount.
+    burn_share_bps: u16,
+    /// Whether transfers to contract accounts require their acknowledgement.
+    require_receiver_acknowledgement: bool,
+    /// Contract accounts that acknowledged they can handle the token.
+    acknowledged_receivers: LookupSet<AccountId>,
+    /// Account -> whether the owner classified it as a contract, overriding the naming rule.
+    contract_accounts: LookupMap<AccountId, bool>,
+    /// Owner -> nonce its next permit must be signed with.
+    permit_nonces: LookupMap<AccountId, u64>,
+    /// Owner -> ed25519 public key it registered for signing permits.
+    permit_keys: LookupMap<AccountId, Vec<u8>>,
+    /// Account -> `(label, amount)` of the earmarks within its balance.
+    earmarks: LookupMap<AccountId, Vec<(String, Balance)>>,
+    /// (receiver, sender) -> what the receiver accepts from the sender per window.
+    receive_limits: LookupMap<(AccountId, AccountId), receive_limits::ReceiveLimit>,
+    /// NEAR set aside for storage deposits of sponsored registrations.
+    sponsorship_budget: Balance,
+    /// Accounts, like the treasury, whose balances don't count as circulating.
+    excluded_accounts: UnorderedSet<AccountId>,
+    /// Merkle root of the current airdrop round's allocations.
+    airdrop_root: Option<Vec<u8>>,
+    airdrop_round: u32,
+    /// Reserve of the current airdrop round not claimed yet, held in custody.
+    airdrop_remaining: Balance,
+    /// (round, account) pairs that claimed their allocation.
+    airdrop_claimed: LookupSet<(u32, AccountId)>,
 }
 
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml;base64,PD94bWwgdmVyc2lvbj0iMS4wIiBzdGFuZGFsb25lPSJubyI/Pgo8IURPQ1RZUEUgc3ZnIFBVQkxJQyAiLS8vVzNDLy9EVEQgU1ZHIDIwMDEwOTA0Ly9FTiIKICJodHRwOi8vd3d3LnczLm9yZy9UUi8yMDAxL1JFQy1TVkctMjAwMTA5MDQvRFREL3N2ZzEwLmR0ZCI+CjxzdmcgdmVyc2lvbj0iMS4wIiB4bWxucz0iaHR0cDovL3d3dy53My5vcmcvMjAwMC9zdmciCiB3aWR0aD0iMTYxMy4wMDAwMDBwdCIgaGVpZ2h0PSIxNjEzLjAwMDAwMHB0IiB2aWV3Qm94PSIwIDAgMTYxMy4wMDAwMDAgMTYxMy4wMDAwMDAiCiBwcmVzZXJ2ZUFzcGVjdFJhdGlvPSJ4TWlkWU1pZCBtZWV0Ij4KCjxnIHRyYW5zZm9ybT0idHJhbnNsYXRlKDAuMDAwMDAwLDE2MTMuMDAwMDAwKSBzY2FsZSgwLjEwMDAwMCwtMC4xMDAwMDApIgpmaWxsPSIjMDAwMDAwIiBzdHJva2U9Im5vbmUiPgo8cGF0aCBkPSJNOTE4NSA3NTQwIGMtMTM5IC0yNSAtMjUyIC04OSAtMzE2IC0xNzggLTUzIC03NCAtNzMgLTE0NiAtNzMgLTI1MwoxIC03NCA1IC05NSAzMSAtMTUwIDU0IC0xMTcgMTU0IC0xOTAgMzU1IC0yNjMgMTQwIC01MSAyMDAgLTgwIDI0MSAtMTE2IDQ3Ci00MSA2MyAtOTEgNDggLTE0NyAtMjEgLTc2IC04OCAtMTA0IC0yNDYgLTEwNCAtMTE1IDEgLTIwMiAxNyAtMzAyIDU3IC0zNSAxNAotNjYgMjQgLTY3IDIyIC0xIC0xIC0yMCAtNTIgLTQxIC0xMTMgLTIxIC02MCAtNDAgLTExNiAtNDMgLTEyMyAtNiAtMTYgMTAwCi02NCAxOTAgLTg2IDIyMSAtNTUgNDkyIC00MCA2MzkgMzQgNjIgMzIgMTM1IDEwNSAxNjQgMTY0IDc3IDE2MSA0MCAzOTkgLTgxCjUwNSAtNTggNTEgLTE2NSAxMDYgLTMzNyAxNzEgLTE2OSA2NSAtMjE2IDEwMSAtMjI0IDE3MCAtNCAzNiAwIDUyIDE5IDgwIDMyCjQ4IDgxIDYzIDIwMyA2MyA4NSAwIDExMyAtNSAxODUgLTMwIDQ3IC0xNiA5MiAtMzMgMTAxIC0zNyAxMyAtNiAyNCAxNiA2MQoxMjAgbDQ2IDEyNiAtNTUgMjQgYy0xMDMgNDYgLTE5MSA2NSAtMzIzIDY5IC02OSAyIC0xNDcgMCAtMTc1IC01eiIvPgo8cGF0aCBkPSJNNjAzNCA3NTIwIGMtMzkgLTQgLTg5IC0xMSAtMTEyIC0xNSBsLTQyIC03IDAgLTY5OSAwIC02OTkgMjMgLTUKYzEyNiAtMjggNDA2IC00MiA1NDEgLTI1IDIyNSAyNyAzNjYgODcgNDg3IDIwOSAxMjMgMTI0IDE3NSAyNTQgMTg2IDQ2NyA3CjE0MiAtNyAyNTUgLTQ0IDM1OCAtNzUgMjA5IC0yMjUgMzM2IC00NjcgMzk3IC02NiAxNyAtMTIwIDIyIC0yOTEgMjQgLTExNSAyCi0yNDIgMCAtMjgxIC01eiBtNDk2IC0yODkgYzE2NyAtNTQgMjQ4IC0xNzcgMjU4IC0zOTIgNiAtMTM5IC0xNCAtMjM1IC02NwotMzE2IC04NyAtMTM1IC0yMDcgLTE4NiAtNDE5IC0xODEgbC0xMDcgMyAtMyA0NDkgYy0yIDM1MyAxIDQ1MiAxMCA0NTggMjQgMTcKMjU4IDEgMzI4IC0yMXoiLz4KPHBhdGggZD0iTTQ3NzQgNzQyMyBjLTEzMyAtMjg1IC0zMDIgLTcxNSAtNDcwIC0xMTkxIGwtNTMgLTE1MiAxNjYgMiAxNjYgMwo1MyAxNTAgNTIgMTUwIDI3OCAwIDI3OCAwIDUyIC0xNTAgNTIgLTE1MCAxNzEgLTMgYzk0IC0xIDE3MSAtMSAxNzEgMSAwIDEwCi0xNzQgNDg4IC0yNDAgNjYyIC03OCAyMDMgLTIwOSA1MTggLTI4NCA2NzggbC00NSA5NyAtMTUxIDAgLTE1MSAwIC00NSAtOTd6Cm0zMzcgLTYyMSBjMjkgLTc4IDQ5IC0xNDUgNDcgLTE0OCAtMyAtMiAtOTMgLTMgLTIwMCAtMiBsLTE5NSAzIDEwMSAyNjcgMTAwCjI2OCA0OCAtMTIzIGMyNiAtNjcgNzEgLTE4NiA5OSAtMjY1eiIvPgo8cGF0aCBkPSJNNzY5MyA3MzY4IGMtNzIgLTE1NyAtMjE4IC01MTQgLTI5OSAtNzMxIC03MCAtMTg3IC0xOTQgLTUzNyAtMTk0Ci01NDggMCAtNSA2OCAtOSAxNjMgLTkgbDE2MiAwIDU0IDE1NSA1MyAxNTUgMjc3IDAgMjc3IDAgNTMgLTE1NSA1NCAtMTU1IDE2OAowIGM5MyAwIDE2OSA0IDE2OSA4IDAgMTkgLTIwOSA1ODggLTI5NiA4MDYgLTk3IDI0MyAtMjQ4IDU5MCAtMjY2IDYxNCAtOCA4Ci01MiAxMiAtMTU4IDEyIGwtMTQ4IDAgLTY5IC0xNTJ6IG0yODEgLTM0OSBjMzEgLTgxIDc0IC0xOTcgOTYgLTI1OCBsMzkgLTExMQotMjAwIDAgYy0xNjIgMCAtMjAwIDMgLTE5NyAxMyAxMyA1NyAxOTQgNTIyIDE5OSA1MTMgNCAtNiAzMiAtNzcgNjMgLTE1N3oiLz4KPHBhdGggZD0iTTEwMDU3IDc1MTMgYy00IC0zIC03IC0zMjcgLTcgLTcyMCBsMCAtNzEzIDE2MCAwIDE2MCAwIDAgMzA1IDAgMzA1CjI3MCAwIDI3MCAwIDAgLTMwNSAwIC0zMDUgMTYwIDAgMTYwIDAgMCA3MjAgMCA3MjAgLTE2MCAwIC0xNjAgMCAwIC0yNzUgMAotMjc1IC0yNzAgMCAtMjcwIDAgMCAyNzUgMCAyNzUgLTE1MyAwIGMtODUgMCAtMTU3IC0zIC0xNjAgLTd6Ii8+CjxwYXRoIGQ9Ik0xMTU2MCA2ODAwIGwwIC03MjAgMTYwIDAgMTYwIDAgMCA3MjAgMCA3MjAgLTE2MCAwIC0xNjAgMCAwIC03MjB6Ii8+CjxwYXRoIGQ9Ik03ODQxIDU1MjggYy01IC0xMyAtNzUgLTE4OSAtMTU2IC0zOTMgLTgxIC0yMDMgLTE1MyAtMzg3IC0xNjEgLTQwNwpsLTEzIC0zOCA2MiAwIDYyIDAgMzEgODggMzEgODcgMTU4IDAgMTU4IDAgMjYgLTg1IDI3IC04NSA2MiAtMyBjMzQgLTIgNjIgMAo2MiAzIDAgNCAtMjczIDc1MiAtMzA2IDgzOCAtOSAyNCAtMzMgMjEgLTQzIC01eiBtNzggLTM4NSBjMjggLTg4IDUxIC0xNjggNTEKLTE3NyAwIC0xNCAtMTcgLTE2IC0xMjEgLTE2IC05MiAwIC0xMjAgMyAtMTE3IDEzIDE3IDY3IDEyMyAzNTYgMTI5IDM1MCA0IC01CjMwIC04MSA1OCAtMTcweiIvPgo8cGF0aCBkPSJNOTI2MCA1NTQxIGMtMTg0IC01NyAtMjkwIC0yNjQgLTI2MCAtNTEwIDE1IC0xMTggNTYgLTIwNyAxMjcgLTI3Mgo3MCAtNjMgMTM0IC04MyAyNDggLTc3IDg3IDQgMTcyIDM2IDIwOSA3NyAxNyAxOSAxNyAyMSAtMTAgNTggLTE2IDIxIC0yOSA0MAotMzEgNDIgLTEgMiAtMTggLTEwIC0zNyAtMjcgLTY1IC01NyAtMTYxIC02OCAtMjQzIC0yNyAtOTggNDkgLTE0NSAxNDQgLTE0NwoyOTUgLTEgMTY2IDUwIDI3NyAxNTAgMzI2IDQ4IDIzIDYzIDI2IDEyNyAyMiA0MSAtMyA4NyAtMTIgMTA0IC0yMCBsMzEgLTE2CjIxIDQ5IGMxMSAyNyAxNyA1MCAxMyA1MyAtNTAgMzAgLTIzNyA0NyAtMzAyIDI3eiIvPgo8cGF0aCBkPSJNNTkwMCA1MTE1IGwwIC00MjUgNTUgMCA1NSAwIDAgMjA1IDAgMjA1IDE1MCAwIDE1MCAwIDAgNTAgMCA1MAotMTUwIDAgLTE1MSAwIDMgMTE4IDMgMTE3IDIwMyAzIDIwMiAyIDAgNTAgMCA1MCAtMjYwIDAgLTI2MCAwIDAgLTQyNXoiLz4KPHBhdGggZD0iTTY1NDAgNTExNSBsMCAtNDI1IDU1IDAgNTUgMCAwIDQyNSAwIDQyNSAtNTUgMCAtNTUgMCAwIC00MjV6Ii8+CjxwYXRoIGQ9Ik02ODQwIDUxMTUgbDAgLTQyNSA1NSAwIDU1IDAgMCAzMDIgYzAgMjY0IDIgMzAwIDE0IDI4OCA4IC04IDEwNQotMTQ1IDIxNiAtMzA1IDEzMCAtMTg3IDIwOCAtMjkxIDIyMSAtMjkzIDE5IC0zIDE5IDcgMTkgNDI3IGwwIDQzMSAtNTUgMCAtNTUKMCAtMiAtMjkxIC0zIC0yOTEgLTIwOCAyOTEgYy0xNzYgMjQ2IC0yMTIgMjkxIC0yMzIgMjkxIGwtMjUgMCAwIC00MjV6Ii8+CjxwYXRoIGQ9Ik04MjkwIDUxMTYgbDAgLTQyNiA1MCAwIDUwIDAgMCAzMDAgYzAgMTY1IDMgMzAwIDggMjk5IDQgMCAxMDIgLTEzNwoyMTcgLTMwNCAxMzkgLTIwMiAyMTYgLTMwNSAyMjggLTMwNSAxNiAwIDE3IDI3IDE3IDQzMCBsMCA0MzAgLTU1IDAgLTU1IDAgLTIKLTI5MCAtMyAtMjkwIC0yMDYgMjg4IGMtMTQ4IDIwNyAtMjExIDI4OCAtMjI3IDI5MCBsLTIyIDMgMCAtNDI1eiIvPgo8cGF0aCBkPSJNOTc0MCA1MTE1IGwwIC00MjUgMjQ1IDAgMjQ1IDAgMCA1MCAwIDUwIC0xOTAgMCAtMTkwIDAgMCAxNTUgMCAxNTUKMTM1IDAgMTM1IDAgMCA1MCAwIDUwIC0xMzUgMCAtMTM1IDAgMCAxMjAgMCAxMjAgMTkwIDAgMTkwIDAgMCA1MCAwIDUwIC0yNDUKMCAtMjQ1IDAgMCAtNDI1eiIvPgo8L2c+Cjwvc3ZnPgo=";
@@ -36,7 +190,8 @@ const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml;base64,PD94bWwgdmVyc2
 #[near_bindgen]
 impl Contract {
     /// Initializes the contract with the given total supply owned by the given `owner_id` with
-    /// default metadata (for example purposes only).
+    /// default metadata (for example purposes only). The supply is left uncapped; deployments
+    /// that need a hard ceiling should use `new` with an explicit `max_supply`.
     #[init]
     pub fn new_default_meta(owner_id: AccountId, total_supply: U128) -> Self {
         Self::new(
@@ -51,25 +206,84 @@ impl Contract {
                 reference_hash: None,
                 decimals: 24,
             },
+            None,
         )
     }
 
     /// Initializes the contract with the given total supply owned by the given `owner_id` with
-    /// the given fungible token metadata.
+    /// the given fungible token metadata. When `max_supply` is set, no mint can ever take the
+    /// total supply above it.
     #[init]
     pub fn new(
         owner_id: AccountId,
         total_supply: U128,
         metadata: FungibleTokenMetadata,
+        max_supply: Option<U128>,
     ) -> Self {
         assert!(!env::state_exists(), "Already initialized");
         metadata.assert_valid();
+        if let Some(max_supply) = max_supply {
+            assert!(total_supply.0 <= max_supply.0, "Total supply exceeds the max supply");
+        }
         let mut this = Self {
+            owner_id: owner_id.clone(),
+            pending_owner: None,
             token: FungibleToken::new(b"a".to_vec()),
             metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            max_supply: max_supply.map(|max_supply| max_supply.into()),
+            minters: UnorderedSet::new(b"n".to_vec()),
+            minting_renounced: false,
+            paused: false,
+            display_decimals: metadata.decimals,
+            blacklist: LookupSet::new(b"b".to_vec()),
+            receive_consent_thresholds: LookupMap::new(b"t".to_vec()),
+            receive_consents: LookupSet::new(b"c".to_vec()),
+            snapshots: LookupMap::new(b"s".to_vec()),
+            supply_snapshots: Vector::new(b"u".to_vec()),
+            last_proposal_snapshot: None,
+            receipt_opt_ins: LookupSet::new(b"o".to_vec()),
+            receipts: LookupMap::new(b"r".to_vec()),
+            delegates: LookupMap::new(b"d".to_vec()),
+            voting_power: LookupMap::new(b"w".to_vec()),
+            min_delegation_age_ms: 0,
+            delegated_at: LookupMap::new(b"i".to_vec()),
+            recent_delegators: LookupMap::new(b"j".to_vec()),
+            vesting: LookupMap::new(b"g".to_vec()),
+            locked: LookupMap::new(b"l".to_vec()),
+            min_lock_amount: 10u128.saturating_pow(metadata.decimals.into()),
+            allowances: LookupMap::new(b"e".to_vec()),
+            proposal_threshold: 0,
+            proposals: Vector::new(b"p".to_vec()),
+            voted: LookupSet::new(b"q".to_vec()),
+            voting_history: LookupMap::new(b"y".to_vec()),
+            stakes: LookupMap::new(b"k".to_vec()),
+            total_staked: 0,
+            reward_per_token: 0,
+            reward_pool: 0,
+            holders: Vector::new(b"h".to_vec()),
+            holder_index: LookupMap::new(b"x".to_vec()),
+            fee_basis_points: 0,
+            fee_treasury: owner_id.clone(),
+            burn_share_bps: 0,
+            require_receiver_acknowledgement: false,
+            acknowledged_receivers: LookupSet::new(b"f".to_vec()),
+            contract_accounts: LookupMap::new(b"C".to_vec()),
+            permit_nonces: LookupMap::new(b"z".to_vec()),
+            permit_keys: LookupMap::new(b"P".to_vec()),
+            earmarks: LookupMap::new(b"E".to_vec()),
+            receive_limits: LookupMap::new(b"T".to_vec()),
+            sponsorship_budget: 0,
+            excluded_accounts: UnorderedSet::new(b"X".to_vec()),
+            airdrop_root: None,
+            airdrop_round: 0,
+            airdrop_remaining: 0,
+            airdrop_claimed: LookupSet::new(b"A".to_vec()),
         };
-        this.token.internal_register_account(&owner_id);
+        this.token.account_storage_usage += holders::HOLDER_STORAGE_USAGE;
+        this.internal_register_account(&owner_id);
         this.token.internal_deposit(&owner_id, total_supply.into());
+        this.record_balance_snapshots(&[&owner_id]);
+        this.record_supply_snapshot();
         near_contract_standards::fungible_token::events::FtMint {
             owner_id: &owner_id,
             amount: &total_supply,
@@ -79,17 +293,333 @@ impl Contract {
         this
     }
 
+    /// Sets the number of decimals UIs should use for formatting. On-chain arithmetic always uses
+    /// the full `decimals` from the metadata.
+    #[private]
+    pub fn set_display_decimals(&mut self, display_decimals: u8) {
+        let decimals = self.metadata.get().unwrap().decimals;
+        assert!(
+            display_decimals <= decimals,
+            "Display decimals {} exceed token decimals {}",
+            display_decimals,
+            decimals
+        );
+        self.display_decimals = display_decimals;
+    }
+
+    pub fn ft_display_decimals(&self) -> u8 {
+        self.display_decimals
+    }
+
+    /// Returns whether an account-specific restriction currently blocks every outgoing transfer of
+    /// `account_id`: it is blacklisted, or its whole balance is earmarked. The global pause is not
+    /// taken into account; see `is_paused`.
+    pub fn is_transfer_restricted(&self, account_id: AccountId) -> bool {
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        self.blacklist.contains(&account_id)
+            || (balance > 0 && self.free_balance_of(account_id).0 == 0)
+    }
+
+    /// Computes the outcome of applying `ops` as sequential `(sender, receiver, amount)` transfers
+    /// without mutating state. Each op sees the balances left by the successful ops before it and
+    /// is checked with the same guards as `ft_transfer` and pays the same transfer fee. Returns the
+    /// amount credited to the receiver after the fee on success or the panic message the real
+    /// transfer would fail with.
+    pub fn simulate_transfers(
+        &self,
+        ops: Vec<(AccountId, AccountId, U128)>,
+    ) -> Vec<Result<U128, String>> {
+        let mut balances: HashMap<AccountId, Balance> = HashMap::new();
+        let mut limited_receives: HashMap<(AccountId, AccountId), Balance> = HashMap::new();
+        ops.into_iter()
+            .map(|(sender_id, receiver_id, amount)| {
+                let amount: Balance = amount.into();
+                let sender_balance = self.simulated_balance_of(&balances, &sender_id).unwrap_or(0);
+                let limit_key = (receiver_id.clone(), sender_id.clone());
+                let unrecorded = limited_receives.get(&limit_key).copied().unwrap_or(0);
+                self.check_transfer(&sender_id, &receiver_id, amount, sender_balance, unrecorded)?;
+                if sender_id == receiver_id {
+                    return Err("Sender and receiver should be different".to_string());
+                }
+                if amount == 0 {
+                    return Err("The amount should be a positive number".to_string());
+                }
+                let (burned, mut to_treasury) = self.transfer_fee(amount);
+                if burned + to_treasury > 0 && amount <= burned + to_treasury {
+                    return Err("The amount doesn't cover the transfer fee".to_string());
+                }
+                let received = amount - burned - to_treasury;
+                if sender_id == self.fee_treasury {
+                    to_treasury = 0;
+                }
+                let sender_balance = self.simulated_balance_of(&balances, &sender_id)?;
+                let new_sender_balance = sender_balance
+                    .checked_sub(received + burned + to_treasury)
+                    .ok_or_else(|| "The account doesn't have enough balance".to_string())?;
+                let receiver_balance = self.simulated_balance_of(&balances, &receiver_id)?;
+                let new_receiver_balance = receiver_balance
+                    .checked_add(received)
+                    .ok_or_else(|| "Balance overflow".to_string())?;
+                limited_receives.insert(limit_key, unrecorded + amount);
+                balances.insert(sender_id, new_sender_balance);
+                balances.insert(receiver_id, new_receiver_balance);
+                if to_treasury > 0 {
+                    let treasury_balance =
+                        self.simulated_balance_of(&balances, &self.fee_treasury)?;
+                    balances.insert(self.fee_treasury.clone(), treasury_balance + to_treasury);
+                }
+                Ok(received.into())
+            })
+            .collect()
+    }
+
+    fn simulated_balance_of(
+        &self,
+        balances: &HashMap<AccountId, Balance>,
+        account_id: &AccountId,
+    ) -> Result<Balance, String> {
+        match balances.get(account_id) {
+            Some(balance) => Ok(*balance),
+            None => self
+                .token
+                .accounts
+                .get(account_id)
+                .ok_or_else(|| format!("The account {} is not registered", account_id)),
+        }
+    }
+
+    /// Contract-level guards applied on top of the standard NEP-141 transfer checks, with the
+    /// sender holding `sender_balance` and `unrecorded` more counting toward its receive limit.
+    fn check_transfer(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        sender_balance: Balance,
+        unrecorded: Balance,
+    ) -> Result<(), String> {
+        if self.paused {
+            return Err("Transfers are paused".to_string());
+        }
+        self.check_not_blacklisted(&[sender_id, receiver_id])?;
+        self.check_receive_consent(sender_id, receiver_id, amount)?;
+        self.check_receive_limit(sender_id, receiver_id, amount, unrecorded)?;
+        self.check_receiver_acknowledgement(receiver_id)?;
+        self.check_free_balance(sender_id, sender_balance, amount)
+    }
+
+    /// Asserts that the transfer passes `check_transfer` and counts it toward the receive limits.
+    fn assert_transfer(&mut self, sender_id: &AccountId, receiver_id: &AccountId, amount: Balance) {
+        let sender_balance = self.token.accounts.get(sender_id).unwrap_or(0);
+        if let Err(message) = self.check_transfer(sender_id, receiver_id, amount, sender_balance, 0)
+        {
+            panic!("{}", message);
+        }
+        self.record_limited_receive(sender_id, receiver_id, amount);
+    }
+
+    fn assert_owner(&self) {
+        assert!(
+            env::predecessor_account_id() == self.owner_id,
+            "Only the owner can call this method"
+        );
+    }
+
+    fn assert_registered(&self, account_id: &AccountId) {
+        assert!(
+            self.token.accounts.contains_key(account_id),
+            "The account {} is not registered",
+            account_id
+        );
+    }
+
+    /// Registers the contract's own account, which holds tokens locked on behalf of others, and
+    /// returns its id.
+    fn register_custody_account(&mut self) -> AccountId {
+        let contract_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&contract_id) {
+            self.internal_register_account(&contract_id);
+        }
+        contract_id
+    }
+
+    /// Moves `amount` between two registered accounts on the contract's own initiative, keeping
+    /// the balance snapshots and delegated votes in sync.
+    fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        self.assert_not_blacklisted(&[sender_id, receiver_id]);
+        self.assert_free_balance(sender_id, amount);
+        self.token.internal_transfer(sender_id, receiver_id, amount, memo);
+        self.record_balance_snapshots(&[sender_id, receiver_id]);
+        self.move_voting_power(Some(sender_id), Some(receiver_id), amount);
+    }
+
     fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
-        log!("Closed @{} with {}", account_id, balance);
+        self.record_balance_snapshots(&[&account_id]);
+        self.record_supply_snapshot();
+        self.move_voting_power(Some(&account_id), None, balance);
+        self.delegates.remove(&account_id);
+        self.delegated_at.remove(&account_id);
+        self.remove_holder(&account_id);
+        self.earmarks.remove(&account_id);
+        self.clear_receipts(&account_id);
+        events::AccountClosed { account_id: &account_id, balance: &U128(balance) }.emit();
     }
 
     fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
-        log!("Account @{} burned {}", account_id, amount);
+        self.record_supply_snapshot();
+        events::TokensBurned { account_id: &account_id, amount: &U128(amount) }.emit();
+    }
+}
+
+/// Debug setters for arranging state directly. Only compiled with the `testing` feature, so they
+/// are absent from release wasm builds.
+#[cfg(feature = "testing")]
+#[near_bindgen]
+impl Contract {
+    /// Overwrites the total supply without touching any balance.
+    #[private]
+    pub fn debug_set_total_supply(&mut self, new_supply: U128) {
+        self.token.total_supply = new_supply.into();
+        self.record_supply_snapshot();
+    }
+
+    /// Overwrites the balance of `account_id`, registering it if needed. The total supply is
+    /// adjusted by the difference so it stays equal to the sum of balances.
+    #[private]
+    pub fn debug_set_balance(&mut self, account_id: AccountId, amount: U128) {
+        let old_balance = match self.token.accounts.get(&account_id) {
+            Some(balance) => balance,
+            None => {
+                self.add_holder(&account_id);
+                0
+            }
+        };
+        self.token.accounts.insert(&account_id, &amount.0);
+        self.token.total_supply = self.token.total_supply - old_balance + amount.0;
+        self.record_balance_snapshots(&[&account_id]);
+        self.record_supply_snapshot();
+        if amount.0 > old_balance {
+            self.move_voting_power(None, Some(&account_id), amount.0 - old_balance);
+        } else {
+            self.move_voting_power(Some(&account_id), None, old_balance - amount.0);
+        }
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+    #[payable]
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        let sender_id = env::predecessor_account_id();
+        self.assert_transfer(&sender_id, &receiver_id, amount.into());
+        let received = self.amount_after_fee(amount.into());
+        self.token.ft_transfer(receiver_id.clone(), received.into(), memo.clone());
+        self.record_balance_snapshots(&[&sender_id, &receiver_id]);
+        self.move_voting_power(Some(&sender_id), Some(&receiver_id), received);
+        self.charge_transfer_fee(&sender_id, amount.into());
+        self.record_receipt(&sender_id, &receiver_id, received, memo);
+    }
+
+    #[payable]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let sender_id = env::predecessor_account_id();
+        self.assert_transfer(&sender_id, &receiver_id, amount.into());
+        // The receiver is offered what is left after the fee, and the fee isn't refunded with
+        // whatever it returns.
+        let received = self.amount_after_fee(amount.into());
+        let result =
+            self.token.ft_transfer_call(receiver_id.clone(), received.into(), memo.clone(), msg);
+        self.record_balance_snapshots(&[&sender_id, &receiver_id]);
+        self.move_voting_power(Some(&sender_id), Some(&receiver_id), received);
+        self.charge_transfer_fee(&sender_id, amount.into());
+        self.record_receipt(&sender_id, &receiver_id, received, memo);
+        result
+    }
+
+    fn ft_total_supply(&self) -> U128 {
+        self.token.ft_total_supply()
+    }
+
+    fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        self.token.ft_balance_of(account_id)
     }
 }
 
-near_contract_standards::impl_fungible_token_core!(Contract, token, on_tokens_burned);
-near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
+#[near_bindgen]
+impl FungibleTokenResolver for Contract {
+    #[private]
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        let (used_amount, burned_amount) =
+            self.token.internal_ft_resolve_transfer(&sender_id, receiver_id.clone(), amount);
+        self.record_balance_snapshots(&[&sender_id, &receiver_id]);
+        if burned_amount > 0 {
+            self.move_voting_power(Some(&receiver_id), None, burned_amount);
+            self.on_tokens_burned(sender_id, burned_amount);
+        } else {
+            self.move_voting_power(Some(&receiver_id), Some(&sender_id), amount.0 - used_amount);
+        }
+        used_amount.into()
+    }
+}
+
+#[near_bindgen]
+impl StorageManagement for Contract {
+    #[payable]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let registered = self.token.accounts.contains_key(&account_id);
+        let storage_balance =
+            self.token.storage_deposit(Some(account_id.clone()), registration_only);
+        if !registered {
+            self.add_holder(&account_id);
+        }
+        storage_balance
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        self.token.storage_withdraw(amount)
+    }
+
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        if let Some((account_id, balance)) = self.token.internal_storage_unregister(force) {
+            self.on_account_closed(account_id, balance);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        self.token.storage_balance_bounds()
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.token.storage_balance_of(account_id)
+    }
+}
 
 #[near_bindgen]
 impl FungibleTokenMetadataProvider for Contract {
@@ -101,14 +631,14 @@ impl FungibleTokenMetadataProvider for Contract {
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::MockedBlockchain;
     use near_sdk::{testing_env, Balance};
 
     use super::*;
 
-    const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
+    pub(crate) const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
+    pub(crate) const ONE_NEAR: Balance = 1_000_000_000_000_000_000_000_000;
 
-    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+    pub(crate) fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
         let mut builder = VMContextBuilder::new();
         builder
             .current_account_id(accounts(0))
@@ -117,11 +647,31 @@ mod tests {
         builder
     }
 
+    /// Pays for the registration of `account_id` from its own wallet.
+    pub(crate) fn register(contract: &mut Contract, account_id: AccountId) {
+        testing_env!(get_context(account_id)
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .build());
+        contract.storage_deposit(None, None);
+    }
+
+    /// Delegates as `delegator` at the current block and time, paying for the storage.
+    pub(crate) fn delegate_as(contract: &mut Contract, delegator: AccountId, delegatee: AccountId) {
+        testing_env!(get_context(delegator)
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(env::block_height())
+            .block_timestamp(env::block_timestamp())
+            .build());
+        contract.delegate(delegatee);
+    }
+
     #[test]
     fn test_new() {
         let mut context = get_context(accounts(1));
         testing_env!(context.build());
-        let contract = Contract::new_default_meta(accounts(1).into(), TOTAL_SUPPLY.into());
+        let contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
         testing_env!(context.is_view(true).build());
         assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
         assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
@@ -139,7 +689,7 @@ mod tests {
     fn test_transfer() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
         testing_env!(context
             .storage_usage(env::storage_usage())
             .attached_deposit(contract.storage_balance_bounds().min.into())
@@ -165,4 +715,211 @@ mod tests {
         assert_eq!(contract.ft_balance_of(accounts(2)).0, (TOTAL_SUPPLY - transfer_amount));
         assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
     }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_debug_setters() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+
+        contract.debug_set_balance(accounts(2), 500.into());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 500);
+        assert_eq!(contract.holder_count(), 2);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 500);
+
+        contract.debug_set_balance(accounts(1), 100.into());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(contract.ft_total_supply().0, 600);
+
+        contract.debug_set_total_supply(42.into());
+        assert_eq!(contract.ft_total_supply().0, 42);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+    }
+
+    /// Method calls resolve to inherent methods before trait methods, so this only compiles while
+    /// the debug setters are compiled out.
+    #[cfg(not(feature = "testing"))]
+    #[test]
+    fn test_debug_setters_unavailable() {
+        trait Absent {
+            fn debug_set_total_supply(&mut self, _new_supply: U128) -> bool {
+                true
+            }
+            fn debug_set_balance(&mut self, _account_id: AccountId, _amount: U128) -> bool {
+                true
+            }
+        }
+        impl Absent for Contract {}
+
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        let total_supply_absent: bool = contract.debug_set_total_supply(42.into());
+        let balance_absent: bool = contract.debug_set_balance(accounts(2), 42.into());
+        assert!(total_supply_absent && balance_absent);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_simulate_transfers() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        let ops = vec![
+            (accounts(2), accounts(1), U128(TOTAL_SUPPLY / 2)),
+            (accounts(1), accounts(2), U128(TOTAL_SUPPLY / 4)),
+            (accounts(1), accounts(2), U128(TOTAL_SUPPLY / 2)),
+            (accounts(1), accounts(1), U128(1)),
+            (accounts(2), accounts(1), U128(0)),
+            (accounts(2), accounts(3), U128(1)),
+        ];
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        let results = contract.simulate_transfers(ops.clone());
+        assert_eq!(
+            results,
+            vec![
+                Ok(U128(TOTAL_SUPPLY / 2)),
+                Ok(U128(TOTAL_SUPPLY / 4)),
+                Err("The account doesn't have enough balance".to_string()),
+                Err("Sender and receiver should be different".to_string()),
+                Err("The amount should be a positive number".to_string()),
+                Err(format!("The account {} is not registered", accounts(3))),
+            ]
+        );
+        // Simulation must leave state untouched.
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY);
+
+        // Executing the ops the simulation accepted gives the same balances it computed.
+        for (sender_id, receiver_id, amount) in ops.into_iter().take(2) {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .is_view(false)
+                .attached_deposit(1)
+                .predecessor_account_id(sender_id)
+                .build());
+            contract.ft_transfer(receiver_id, amount, None);
+        }
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY / 2 - TOTAL_SUPPLY / 4);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY / 2 + TOTAL_SUPPLY / 4);
+    }
+
+    #[test]
+    fn test_simulate_transfers_with_fee() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        register(&mut contract, accounts(3));
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.set_transfer_fee(500, 200, accounts(3));
+
+        let ops = vec![
+            (accounts(1), accounts(2), U128(1_000)),
+            (accounts(2), accounts(1), U128(950)),
+            (accounts(3), accounts(2), U128(58)),
+            (accounts(2), accounts(1), U128(57)),
+        ];
+        testing_env!(context.is_view(true).build());
+        assert_eq!(
+            contract.simulate_transfers(ops.clone()),
+            vec![
+                Ok(U128(950)),
+                Ok(U128(903)),
+                Ok(U128(56)),
+                Err("The account doesn't have enough balance".to_string()),
+            ]
+        );
+        assert_eq!(
+            contract.simulate_transfers(vec![
+                (accounts(1), accounts(2), U128(1_000)),
+                (accounts(3), accounts(2), U128(32))
+            ]),
+            vec![Ok(U128(950)), Err("The account doesn't have enough balance".to_string())]
+        );
+
+        for (sender_id, receiver_id, amount) in ops.into_iter().take(3) {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .is_view(false)
+                .attached_deposit(1)
+                .predecessor_account_id(sender_id)
+                .build());
+            contract.ft_transfer(receiver_id, amount, None);
+        }
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000 + 903);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 56);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Sender and receiver should be different")]
+    fn test_simulated_failure_matches_transfer() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        let results = contract.simulate_transfers(vec![(accounts(2), accounts(2), U128(1))]);
+        assert_eq!(results, vec![Err("Sender and receiver should be different".to_string())]);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), U128(1), None);
+    }
+
+    #[test]
+    fn test_display_decimals() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        assert_eq!(contract.ft_display_decimals(), 24);
+        contract.set_display_decimals(8);
+        assert_eq!(contract.ft_display_decimals(), 8);
+        assert_eq!(contract.ft_metadata().decimals, 24);
+    }
+
+    #[test]
+    #[should_panic(expected = "Display decimals 25 exceed token decimals 24")]
+    fn test_display_decimals_above_decimals() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.set_display_decimals(25);
+    }
+
+    #[test]
+    fn test_blacklisted_account_is_restricted() {
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        assert!(!contract.is_transfer_restricted(accounts(2)));
+        contract.blacklist_account(accounts(2));
+        assert!(contract.is_transfer_restricted(accounts(2)));
+        contract.unblacklist_account(accounts(2));
+        assert!(!contract.is_transfer_restricted(accounts(2)));
+    }
+
+    #[test]
+    fn test_fully_earmarked_account_is_restricted() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.attached_deposit(ONE_NEAR).build());
+        let mut contract = Contract::new_default_meta(accounts(1), 1_000.into());
+        contract.create_earmark("grants".to_string(), U128(999));
+        assert!(!contract.is_transfer_restricted(accounts(1)));
+        contract.create_earmark("grants".to_string(), U128(1));
+        assert!(contract.is_transfer_restricted(accounts(1)));
+        contract.release_earmark("grants".to_string(), U128(1));
+        assert!(!contract.is_transfer_restricted(accounts(1)));
+    }
+
+    #[test]
+    fn test_pause_is_not_a_restriction() {
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.set_paused(true);
+        assert!(!contract.is_transfer_restricted(accounts(1)));
+    }
 }
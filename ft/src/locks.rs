@@ -0,0 +1,224 @@
+//! Timelocked transfers. The tokens are held on the contract's own account until their unlock
+//! time, so they stay in the total supply but are neither spendable nor voting for the receiver.
+//!
+//! Every pending lock takes a slot of the receiver's bounded lock list, so only senders the
+//! receiver granted receive consent to can lock tokens for it, and each lock must be at least
+//! `min_lock_amount`. This is the same consent that lets a sender exceed the receiver's consent
+//! threshold (see `grant_receive_consent`): granting it allows both kinds of transfer.
+use near_sdk::collections::Vector;
+
+use crate::*;
+
+/// Caps the pending locks of one account so releasing them always fits in a single call.
+pub const MAX_LOCKS_PER_ACCOUNT: u64 = 50;
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers `amount` from the caller to `receiver_id`, less the transfer fee, locked until
+    /// the block timestamp (in nanoseconds) reaches `unlock_timestamp`. The receiver must have
+    /// granted the caller receive consent. Requires at least 1 yoctoNEAR, plus the storage cost of
+    /// the lock; the excess is refunded.
+    #[payable]
+    pub fn transfer_with_lock(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        unlock_timestamp: u64,
+    ) {
+        let sender_id = env::predecessor_account_id();
+        assert!(sender_id != receiver_id, "Sender and receiver should be different");
+        assert!(
+            amount.0 >= self.min_lock_amount && amount.0 > 0,
+            "The amount should be at least {}",
+            self.min_lock_amount.max(1)
+        );
+        assert!(
+            unlock_timestamp > env::block_timestamp(),
+            "The unlock timestamp should be in the future"
+        );
+        self.assert_registered(&receiver_id);
+        assert!(
+            self.receive_consents.contains(&(receiver_id.clone(), sender_id.clone())),
+            "Receiver {} has not consented to timelocked transfers from {}",
+            receiver_id,
+            sender_id
+        );
+        self.assert_transfer(&sender_id, &receiver_id, amount.0);
+        let initial_storage_usage = env::storage_usage();
+
+        let mut locks = self.locked.get(&receiver_id).unwrap_or_else(|| {
+            Vector::new([b"L".as_ref(), &env::sha256(receiver_id.as_bytes())].concat())
+        });
+        assert!(
+            locks.len() < MAX_LOCKS_PER_ACCOUNT,
+            "The account {} has too many pending locks",
+            receiver_id
+        );
+        let contract_id = self.register_custody_account();
+        let received = self.internal_transfer_with_fee(
+            &sender_id,
+            &contract_id,
+            amount.0,
+            Some(format!("Locked for @{} until {}", receiver_id, unlock_timestamp)),
+        );
+        locks.push(&(unlock_timestamp, received));
+        self.locked.insert(&receiver_id, &locks);
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Moves every lock of the caller that has reached its unlock time into its free balance and
+    /// returns the released amount.
+    pub fn release_locked(&mut self) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let mut locks = self.locked.get(&account_id).expect("No locked tokens");
+        let now = env::block_timestamp();
+        let mut released: Balance = 0;
+        let mut index = 0;
+        while index < locks.len() {
+            let (unlock_timestamp, amount) = locks.get(index).unwrap();
+            if unlock_timestamp <= now {
+                released += amount;
+                locks.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+        assert!(released > 0, "Nothing to release yet");
+        if locks.is_empty() {
+            self.locked.remove(&account_id);
+        } else {
+            self.locked.insert(&account_id, &locks);
+        }
+        self.internal_transfer(
+            &env::current_account_id(),
+            &account_id,
+            released,
+            Some("Released locked tokens".to_string()),
+        );
+        released.into()
+    }
+
+    pub fn set_min_lock_amount(&mut self, min_lock_amount: U128) {
+        self.assert_owner();
+        self.min_lock_amount = min_lock_amount.into();
+    }
+
+    pub fn min_lock_amount(&self) -> U128 {
+        self.min_lock_amount.into()
+    }
+
+    /// Returns the tokens locked for `account_id` that have not been released yet, including the
+    /// ones past their unlock time.
+    pub fn locked_balance_of(&self, account_id: AccountId) -> U128 {
+        self.locked
+            .get(&account_id)
+            .map_or(0, |locks| locks.iter().map(|(_, amount)| amount).sum())
+            .into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.set_min_lock_amount(U128(10));
+        register(&mut contract, accounts(2));
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.grant_receive_consent(accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_timestamp(100)
+            .build());
+        contract.transfer_with_lock(accounts(2), U128(30), 200);
+        contract.transfer_with_lock(accounts(2), U128(70), 300);
+        contract
+    }
+
+    fn release_at(contract: &mut Contract, timestamp: u64) -> Balance {
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .block_timestamp(timestamp)
+            .build());
+        contract.release_locked().0
+    }
+
+    #[test]
+    fn test_locked_tokens_are_not_spendable() {
+        let contract = setup();
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 100);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.locked_balance_of(accounts(2)).0, 100);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_release_locked() {
+        let mut contract = setup();
+        assert_eq!(release_at(&mut contract, 250), 30);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 30);
+        assert_eq!(contract.locked_balance_of(accounts(2)).0, 70);
+
+        assert_eq!(release_at(&mut contract, 300), 70);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 100);
+        assert_eq!(contract.locked_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to release yet")]
+    fn test_release_before_unlock() {
+        let mut contract = setup();
+        release_at(&mut contract, 199);
+    }
+
+    #[test]
+    #[should_panic(expected = "The unlock timestamp should be in the future")]
+    fn test_lock_in_the_past() {
+        let mut contract = setup();
+        contract.transfer_with_lock(accounts(2), U128(10), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount should be at least 10")]
+    fn test_lock_below_minimum() {
+        let mut contract = setup();
+        contract.transfer_with_lock(accounts(2), U128(9), 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver danny has not consented to timelocked transfers from bob")]
+    fn test_lock_without_consent() {
+        let mut contract = setup();
+        register(&mut contract, accounts(3));
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_timestamp(100)
+            .build());
+        contract.transfer_with_lock(accounts(3), U128(10), 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_lock_needs_storage() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_timestamp(100)
+            .build());
+        contract.transfer_with_lock(accounts(2), U128(10), 200);
+    }
+}
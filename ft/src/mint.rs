@@ -0,0 +1,263 @@
+//! Issuing new ADH after initialization.
+use near_contract_standards::fungible_token::events::FtMint;
+use near_contract_standards::storage_management::StorageManagement;
+use near_sdk::Promise;
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Mints `amount` new tokens to `receiver_id`. Only the owner and registered minters can mint.
+    ///
+    /// An unregistered receiver is registered on the spot, which requires attaching at least
+    /// `storage_balance_bounds().min`. Any unused deposit is refunded to the caller.
+    #[payable]
+    pub fn ft_mint(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        let minter = env::predecessor_account_id();
+        assert!(
+            minter == self.owner_id || self.minters.contains(&minter),
+            "Only the owner or a minter can mint"
+        );
+        let mut refund = env::attached_deposit();
+        if !self.token.accounts.contains_key(&receiver_id) {
+            let min_balance = self.token.storage_balance_bounds().min.0;
+            assert!(
+                refund >= min_balance,
+                "The attached deposit is less than the minimum storage balance"
+            );
+            self.internal_register_account(&receiver_id);
+            refund -= min_balance;
+        }
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        self.internal_mint(&receiver_id, amount.into(), memo.as_deref());
+    }
+
+    /// Lets `account_id`, e.g. a bridge or a rewards distributor, mint alongside the owner.
+    pub fn add_minter(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.minters.insert(&account_id) {
+            log!("Added minter @{}", account_id);
+        }
+    }
+
+    pub fn remove_minter(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.minters.remove(&account_id) {
+            log!("Removed minter @{}", account_id);
+        }
+    }
+
+    /// Returns whether `account_id` can mint. Always true for the owner.
+    pub fn is_minter(&self, account_id: AccountId) -> bool {
+        account_id == self.owner_id || self.minters.contains(&account_id)
+    }
+
+    /// Returns the registered minters, not including the owner.
+    pub fn get_minters(&self) -> Vec<AccountId> {
+        self.minters.to_vec()
+    }
+
+    /// Returns the hard cap on the total supply, or `None` if the supply is uncapped.
+    pub fn ft_max_supply(&self) -> Option<U128> {
+        self.max_supply.map(|max_supply| max_supply.into())
+    }
+
+    /// Permanently disables minting, fixing the supply for good. This can't be undone.
+    pub fn renounce_minting(&mut self) {
+        self.assert_owner();
+        if !self.minting_renounced {
+            self.minting_renounced = true;
+            log!(
+                "MINTING PERMANENTLY RENOUNCED by @{}: the total supply of {} can only decrease",
+                self.owner_id,
+                self.token.total_supply
+            );
+        }
+    }
+
+    pub fn is_minting_renounced(&self) -> bool {
+        self.minting_renounced
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_mint(
+        &mut self,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<&str>,
+    ) {
+        assert!(!self.minting_renounced, "Minting has been permanently disabled");
+        self.assert_not_blacklisted(&[receiver_id]);
+        if let Some(max_supply) = self.max_supply {
+            let new_total_supply = self.token.total_supply.saturating_add(amount);
+            assert!(
+                new_total_supply <= max_supply,
+                "Minting would bring the total supply to {}, above the max supply of {}",
+                new_total_supply,
+                max_supply
+            );
+        }
+        self.token.internal_deposit(receiver_id, amount);
+        self.record_balance_snapshots(&[receiver_id]);
+        self.record_supply_snapshot();
+        self.move_voting_power(None, Some(receiver_id), amount);
+        FtMint { owner_id: receiver_id, amount: &U128(amount), memo }.emit();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::metadata::FT_METADATA_SPEC;
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    #[test]
+    fn test_mint_to_registered() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.ft_mint(accounts(2), U128(500), Some("grant".to_string()));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 500);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 500);
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"charlie","amount":"500","memo":"grant"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mint_registers_receiver() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .build());
+        contract.ft_mint(accounts(3), U128(7), None);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 7);
+        assert!(contract.storage_balance_of(accounts(3)).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn test_mint_unregistered_without_deposit() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.ft_mint(accounts(3), U128(7), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a minter can mint")]
+    fn test_mint_by_non_owner() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.ft_mint(accounts(1), U128(1), None);
+    }
+
+    #[test]
+    fn test_minter_registry() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        assert!(contract.is_minter(accounts(1)));
+        contract.add_minter(accounts(3));
+        assert!(contract.is_minter(accounts(3)));
+        assert_eq!(contract.get_minters(), vec![accounts(3)]);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.ft_mint(accounts(1), U128(5), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY + 5);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.remove_minter(accounts(3));
+        assert!(!contract.is_minter(accounts(3)));
+        assert!(contract.get_minters().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a minter can mint")]
+    fn test_mint_by_removed_minter() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.add_minter(accounts(3));
+        contract.remove_minter(accounts(3));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_mint(accounts(1), U128(5), None);
+    }
+
+    fn new_capped(max_supply: Balance) -> Contract {
+        let metadata = FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Adashi Finance".to_string(),
+            symbol: "ADH".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+        };
+        Contract::new(accounts(1), TOTAL_SUPPLY.into(), metadata, Some(max_supply.into()))
+    }
+
+    #[test]
+    fn test_mint_up_to_max_supply() {
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = new_capped(TOTAL_SUPPLY + 100);
+        assert_eq!(contract.ft_max_supply(), Some(U128(TOTAL_SUPPLY + 100)));
+        contract.ft_mint(accounts(1), U128(100), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 100);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Minting would bring the total supply to 1000000000000101, above the max supply of 1000000000000100"
+    )]
+    fn test_mint_above_max_supply() {
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = new_capped(TOTAL_SUPPLY + 100);
+        contract.ft_mint(accounts(1), U128(101), None);
+    }
+
+    #[test]
+    fn test_uncapped_by_default() {
+        testing_env!(get_context(accounts(1)).build());
+        let contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        assert_eq!(contract.ft_max_supply(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minting has been permanently disabled")]
+    fn test_mint_after_renouncing() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.add_minter(accounts(2));
+        contract.renounce_minting();
+        assert!(contract.is_minting_renounced());
+        assert_eq!(
+            get_logs().last().unwrap(),
+            "MINTING PERMANENTLY RENOUNCED by @bob: the total supply of 1000000000000000 can only decrease"
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.ft_mint(accounts(1), U128(1), None);
+    }
+}
@@ -0,0 +1,93 @@
+//! Two-step ownership transfer. The new owner has to accept the role from its own account, so a
+//! mistyped address can never take over the contract.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Nominates `new_owner`. The current owner stays in charge until the nominee accepts.
+    pub fn propose_owner(&mut self, new_owner: AccountId) {
+        self.assert_owner();
+        log!("@{} proposed @{} as the new owner", self.owner_id, new_owner);
+        self.pending_owner = Some(new_owner);
+    }
+
+    pub fn accept_ownership(&mut self) {
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.pending_owner.as_ref() == Some(&account_id),
+            "Only the pending owner can accept ownership"
+        );
+        log!("@{} accepted ownership from @{}", account_id, self.owner_id);
+        self.owner_id = account_id;
+        self.pending_owner = None;
+    }
+
+    pub fn cancel_ownership_transfer(&mut self) {
+        self.assert_owner();
+        if let Some(pending_owner) = self.pending_owner.take() {
+            log!("Ownership transfer to @{} cancelled", pending_owner);
+        }
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup() -> Contract {
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.propose_owner(accounts(2));
+        contract
+    }
+
+    #[test]
+    fn test_two_step_transfer() {
+        let mut contract = setup();
+        assert_eq!(contract.get_owner(), accounts(1));
+        assert_eq!(contract.get_pending_owner(), Some(accounts(2)));
+
+        testing_env!(get_context(accounts(2)).build());
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner(), accounts(2));
+        assert_eq!(contract.get_pending_owner(), None);
+        assert_eq!(get_logs(), vec!["@charlie accepted ownership from @bob"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept ownership")]
+    fn test_accept_by_other_account() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(3)).build());
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept ownership")]
+    fn test_accept_after_cancel() {
+        let mut contract = setup();
+        contract.cancel_ownership_transfer();
+        testing_env!(get_context(accounts(2)).build());
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_propose_by_non_owner() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2)).build());
+        contract.propose_owner(accounts(2));
+    }
+}
@@ -0,0 +1,76 @@
+//! Global transfer pause for security incidents and migrations.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Pauses or resumes all transfers. Only the owner can toggle the pause.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.assert_owner();
+        self.paused = paused;
+        log!("Transfers {}", if paused { "paused" } else { "resumed" });
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+impl Contract {
+    /// Asserts that transfers aren't paused, for calls that move tokens outside `check_transfer`.
+    pub(crate) fn assert_not_paused(&self) {
+        assert!(!self.paused, "Transfers are paused");
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup_paused() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.set_paused(true);
+        assert!(contract.is_paused());
+        testing_env!(context.attached_deposit(1).build());
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfers are paused")]
+    fn test_transfer_while_paused() {
+        let mut contract = setup_paused();
+        contract.ft_transfer(accounts(2), U128(10), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfers are paused")]
+    fn test_transfer_call_while_paused() {
+        let mut contract = setup_paused();
+        contract.ft_transfer_call(accounts(2), U128(10), None, String::new());
+    }
+
+    #[test]
+    fn test_unpause_restores_transfers() {
+        let mut contract = setup_paused();
+        contract.set_paused(false);
+        assert!(!contract.is_paused());
+        contract.ft_transfer(accounts(2), U128(10), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_pause_by_non_owner() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.set_paused(true);
+    }
+}
@@ -0,0 +1,205 @@
+//! Signed approvals in the style of EIP-2612 `permit`, so a holder without NEAR for gas can grant
+//! an allowance that a relayer submits on its behalf.
+//!
+//! The owner signs the Borsh serialization of a `PermitMessage` with an ed25519 key. The contract
+//! can't read access keys, so the key must either be the one an implicit owner account is derived
+//! from or one the owner registered with `set_permit_key`. Every permit consumes the owner's
+//! current nonce, so a signature can't be replayed, and the message names this contract, so it
+//! can't be replayed against another token either.
+use std::convert::TryFrom;
+
+use ed25519_dalek::{PublicKey, Signature};
+use near_sdk::json_types::Base64VecU8;
+
+use crate::*;
+
+#[derive(BorshSerialize)]
+pub struct PermitMessage {
+    pub token: AccountId,
+    pub owner: AccountId,
+    pub spender: AccountId,
+    pub amount: Balance,
+    pub nonce: u64,
+    /// Block timestamp, in nanoseconds, after which the permit is rejected.
+    pub deadline: u64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the allowance of `spender` over `owner`'s balance to `amount`, authorized by the
+    /// owner's signature of the corresponding `PermitMessage` instead of a call from the owner.
+    /// The relayer attaches at least 1 yoctoNEAR, plus the storage cost of a new allowance and
+    /// nonce; the excess is refunded.
+    #[payable]
+    pub fn ft_permit(
+        &mut self,
+        owner: AccountId,
+        spender: AccountId,
+        amount: U128,
+        deadline: u64,
+        public_key: Base64VecU8,
+        signature: Base64VecU8,
+    ) {
+        assert!(env::block_timestamp() <= deadline, "The permit has expired");
+        assert!(
+            self.is_permit_key(&owner, &public_key.0),
+            "The key can't sign permits for {}",
+            owner
+        );
+        let nonce = self.permit_nonces.get(&owner).unwrap_or(0);
+        let message = PermitMessage {
+            token: env::current_account_id(),
+            owner: owner.clone(),
+            spender: spender.clone(),
+            amount: amount.0,
+            nonce,
+            deadline,
+        };
+        let verified = PublicKey::from_bytes(&public_key.0).is_ok_and(|public_key| {
+            Signature::try_from(signature.0.as_slice()).is_ok_and(|signature| {
+                public_key.verify_strict(&message.try_to_vec().unwrap(), &signature).is_ok()
+            })
+        });
+        assert!(verified, "Invalid permit signature");
+        let initial_storage_usage = env::storage_usage();
+        self.permit_nonces.insert(&owner, &(nonce + 1));
+        self.internal_approve(owner, spender, amount.into());
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Registers the ed25519 public key that signs the caller's permits, replacing any previous
+    /// one. The caller must be registered and attach at least 1 yoctoNEAR, plus the storage cost
+    /// of a first key; the excess is refunded.
+    #[payable]
+    pub fn set_permit_key(&mut self, public_key: Base64VecU8) {
+        assert!(public_key.0.len() == 32, "An ed25519 public key is 32 bytes");
+        let account_id = env::predecessor_account_id();
+        self.assert_registered(&account_id);
+        let initial_storage_usage = env::storage_usage();
+        self.permit_keys.insert(&account_id, &public_key.0);
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Returns the nonce the next permit of `owner` must be signed with.
+    pub fn ft_permit_nonce(&self, owner: AccountId) -> u64 {
+        self.permit_nonces.get(&owner).unwrap_or(0)
+    }
+}
+
+impl Contract {
+    fn is_permit_key(&self, owner: &AccountId, public_key: &[u8]) -> bool {
+        let implicit_account =
+            public_key.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        owner.as_str() == implicit_account
+            || self.permit_keys.get(owner).is_some_and(|key| key == public_key)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey};
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use super::PermitMessage;
+    use crate::tests::{get_context, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    const DEADLINE: u64 = 1_000;
+
+    fn keys() -> (ExpandedSecretKey, PublicKey) {
+        let secret = SecretKey::from_bytes(&[7; 32]).unwrap();
+        (ExpandedSecretKey::from(&secret), PublicKey::from(&secret))
+    }
+
+    /// Bob registers a permit key and signs a permit for danny to spend 100 with nonce `nonce`.
+    fn setup(nonce: u64) -> (Contract, Base64VecU8, Base64VecU8) {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        let (secret, public) = keys();
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.set_permit_key(public.to_bytes().to_vec().into());
+        let message = PermitMessage {
+            token: accounts(0),
+            owner: accounts(1),
+            spender: accounts(3),
+            amount: 100,
+            nonce,
+            deadline: DEADLINE,
+        };
+        let signature = secret.sign(&message.try_to_vec().unwrap(), &public);
+        // The permit is relayed by charlie.
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(DEADLINE)
+            .build());
+        (contract, public.to_bytes().to_vec().into(), signature.to_bytes().to_vec().into())
+    }
+
+    #[test]
+    fn test_permit_sets_allowance() {
+        let (mut contract, public_key, signature) = setup(0);
+        contract.ft_permit(accounts(1), accounts(3), U128(100), DEADLINE, public_key, signature);
+        assert_eq!(contract.ft_allowance(accounts(1), accounts(3)).0, 100);
+        assert_eq!(contract.ft_permit_nonce(accounts(1)), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid permit signature")]
+    fn test_reused_permit() {
+        let (mut contract, public_key, signature) = setup(0);
+        contract.ft_permit(
+            accounts(1),
+            accounts(3),
+            U128(100),
+            DEADLINE,
+            public_key.clone(),
+            signature.clone(),
+        );
+        contract.ft_permit(accounts(1), accounts(3), U128(100), DEADLINE, public_key, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid permit signature")]
+    fn test_permit_for_another_amount() {
+        let (mut contract, public_key, signature) = setup(0);
+        contract.ft_permit(accounts(1), accounts(3), U128(101), DEADLINE, public_key, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "The permit has expired")]
+    fn test_expired_permit() {
+        let (mut contract, public_key, signature) = setup(0);
+        testing_env!(get_context(accounts(2)).block_timestamp(DEADLINE + 1).build());
+        contract.ft_permit(accounts(1), accounts(3), U128(100), DEADLINE, public_key, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "The key can't sign permits for charlie")]
+    fn test_permit_with_unregistered_key() {
+        let (mut contract, public_key, signature) = setup(0);
+        contract.ft_permit(accounts(2), accounts(3), U128(100), DEADLINE, public_key, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account charlie is not registered")]
+    fn test_unregistered_permit_key() {
+        let (mut contract, public_key, _) = setup(0);
+        contract.set_permit_key(public_key);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn test_permit_key_without_deposit() {
+        let (mut contract, public_key, _) = setup(0);
+        testing_env!(get_context(accounts(1)).build());
+        contract.set_permit_key(public_key);
+    }
+}
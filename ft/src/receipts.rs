@@ -0,0 +1,268 @@
+//! Opt-in on-chain transfer receipts. An account that enables them gets a durable, queryable
+//! record of its incoming transfers in addition to the NEP-141 events. Each log keeps only the
+//! latest `RECEIPT_LOG_CAPACITY` receipts, overwriting the oldest one once full, so the storage an
+//! account can make the contract hold is bounded. Memos are cut to `MAX_RECEIPT_MEMO_LEN` bytes
+//! for the same reason; the events still carry them in full. Opting in pays for a full log up
+//! front, and opting out clears the log and refunds it.
+use near_sdk::collections::Vector;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, Promise, StorageUsage};
+
+use crate::*;
+
+pub const RECEIPT_LOG_CAPACITY: u64 = 50;
+pub const MAX_RECEIPT_MEMO_LEN: usize = 64;
+
+/// Storage a full receipt log can take, for the longest possible account ids and memos: the
+/// `RECEIPT_LOG_CAPACITY` entries (41 byte key, 161 byte value) and the log record (69 byte key,
+/// 53 byte value), plus the runtime's 40 byte overhead per record.
+pub(crate) const RECEIPT_LOG_STORAGE_USAGE: StorageUsage =
+    RECEIPT_LOG_CAPACITY * (41 + 161 + 40) + (69 + 53 + 40);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Receipt {
+    pub from: AccountId,
+    pub amount: U128,
+    /// Block timestamp of the transfer, in nanoseconds.
+    pub ts: u64,
+    pub memo: Option<String>,
+}
+
+/// Ring buffer of an account's latest receipts.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ReceiptLog {
+    entries: Vector<Receipt>,
+    /// Number of receipts ever appended. The next one goes to `total % RECEIPT_LOG_CAPACITY`.
+    total: u64,
+}
+
+impl ReceiptLog {
+    /// Index into `entries` of the oldest retained receipt.
+    fn start(&self) -> u64 {
+        if self.total > RECEIPT_LOG_CAPACITY {
+            self.total % RECEIPT_LOG_CAPACITY
+        } else {
+            0
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Enables or disables receipts for the caller's incoming transfers. Enabling requires at least
+    /// 1 yoctoNEAR, plus the storage cost of a full log, see `RECEIPT_LOG_STORAGE_USAGE`; the
+    /// excess is refunded. Disabling requires 1 yoctoNEAR, clears the stored receipts and refunds
+    /// that cost.
+    #[payable]
+    pub fn set_store_receipts(&mut self, store_receipts: bool) {
+        let account_id = env::predecessor_account_id();
+        self.assert_registered(&account_id);
+        if store_receipts {
+            let initial_storage_usage = env::storage_usage();
+            let reserved = if self.receipt_opt_ins.insert(&account_id) {
+                RECEIPT_LOG_STORAGE_USAGE
+            } else {
+                0
+            };
+            Self::charge_storage_reserving(initial_storage_usage, reserved);
+        } else {
+            assert_one_yocto();
+            self.clear_receipts(&account_id);
+        }
+    }
+
+    pub fn stores_receipts(&self, account_id: AccountId) -> bool {
+        self.receipt_opt_ins.contains(&account_id)
+    }
+
+    /// Returns up to `limit` of the retained receipts of `account_id`, oldest first, skipping the
+    /// first `from_index`.
+    pub fn get_receipts(&self, account_id: AccountId, from_index: u64, limit: u64) -> Vec<Receipt> {
+        let log = match self.receipts.get(&account_id) {
+            Some(log) => log,
+            None => return vec![],
+        };
+        let len = log.entries.len();
+        (from_index..len.min(from_index.saturating_add(limit)))
+            .map(|i| log.entries.get((log.start() + i) % len).unwrap())
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Opts `account_id` out of receipts, deleting its log and refunding what it paid for it.
+    pub(crate) fn clear_receipts(&mut self, account_id: &AccountId) {
+        if !self.receipt_opt_ins.remove(account_id) {
+            return;
+        }
+        if let Some(mut log) = self.receipts.remove(account_id) {
+            log.entries.clear();
+        }
+        Promise::new(account_id.clone())
+            .transfer(Balance::from(RECEIPT_LOG_STORAGE_USAGE) * env::storage_byte_cost());
+    }
+
+    /// Appends a receipt for a transfer to `receiver_id` if it opted in.
+    pub(crate) fn record_receipt(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        if !self.receipt_opt_ins.contains(receiver_id) {
+            return;
+        }
+        let mut log = self.receipts.get(receiver_id).unwrap_or_else(|| ReceiptLog {
+            entries: Vector::new([b"R".as_ref(), &env::sha256(receiver_id.as_bytes())].concat()),
+            total: 0,
+        });
+        let receipt = Receipt {
+            from: sender_id.clone(),
+            amount: amount.into(),
+            ts: env::block_timestamp(),
+            memo: memo.map(|mut memo| {
+                if memo.len() > MAX_RECEIPT_MEMO_LEN {
+                    let end =
+                        (0..=MAX_RECEIPT_MEMO_LEN).rev().find(|&end| memo.is_char_boundary(end));
+                    memo.truncate(end.unwrap_or(0));
+                }
+                memo
+            }),
+        };
+        if log.entries.len() < RECEIPT_LOG_CAPACITY {
+            log.entries.push(&receipt);
+        } else {
+            log.entries.replace(log.total % RECEIPT_LOG_CAPACITY, &receipt);
+        }
+        log.total += 1;
+        self.receipts.insert(receiver_id, &log);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+    use near_sdk::testing_env;
+
+    use super::{MAX_RECEIPT_MEMO_LEN, RECEIPT_LOG_CAPACITY, RECEIPT_LOG_STORAGE_USAGE};
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        register(&mut contract, accounts(3));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.set_store_receipts(true);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(42)
+            .build());
+        contract
+    }
+
+    #[test]
+    fn test_receipts_only_when_opted_in() {
+        let mut contract = setup();
+        contract.ft_transfer(accounts(2), U128(10), Some("invoice 7".to_string()));
+        contract.ft_transfer(accounts(3), U128(10), None);
+        assert_eq!(
+            contract.get_receipts(accounts(2), 0, 10),
+            vec![Receipt {
+                from: accounts(1),
+                amount: U128(10),
+                ts: 42,
+                memo: Some("invoice 7".to_string())
+            }]
+        );
+        assert!(contract.get_receipts(accounts(3), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_receipts_pagination_and_ring_buffer() {
+        let mut contract = setup();
+        for amount in 1..=RECEIPT_LOG_CAPACITY + 3 {
+            contract.ft_transfer(accounts(2), U128(amount.into()), None);
+        }
+        let amounts = |receipts: Vec<Receipt>| -> Vec<u128> {
+            receipts.into_iter().map(|receipt| receipt.amount.0).collect()
+        };
+        assert_eq!(amounts(contract.get_receipts(accounts(2), 0, 2)), vec![4, 5]);
+        assert_eq!(
+            amounts(contract.get_receipts(accounts(2), RECEIPT_LOG_CAPACITY - 2, 10)),
+            vec![52, 53]
+        );
+        assert_eq!(
+            contract.get_receipts(accounts(2), 0, u64::MAX).len() as u64,
+            RECEIPT_LOG_CAPACITY
+        );
+        assert!(contract.get_receipts(accounts(2), RECEIPT_LOG_CAPACITY, 10).is_empty());
+    }
+
+    #[test]
+    fn test_long_memo_is_truncated() {
+        let mut contract = setup();
+        contract.ft_transfer(
+            accounts(2),
+            U128(10),
+            Some(format!("x{}", "é".repeat(MAX_RECEIPT_MEMO_LEN))),
+        );
+        let memo = contract.get_receipts(accounts(2), 0, 1)[0].memo.clone().unwrap();
+        // The cut falls inside a two byte character, so it moves back to the character boundary.
+        assert_eq!(memo, format!("x{}", "é".repeat(MAX_RECEIPT_MEMO_LEN / 2 - 1)));
+    }
+
+    #[test]
+    fn test_full_log_fits_its_reserve() {
+        let mut contract = setup();
+        let initial_storage_usage = env::storage_usage();
+        for _ in 0..RECEIPT_LOG_CAPACITY {
+            contract.ft_transfer(accounts(2), U128(10), Some("m".repeat(MAX_RECEIPT_MEMO_LEN)));
+        }
+        assert!(env::storage_usage() - initial_storage_usage <= RECEIPT_LOG_STORAGE_USAGE);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_opt_in_pays_for_the_log() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(3))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.set_store_receipts(true);
+    }
+
+    #[test]
+    fn test_opt_out_clears_and_refunds() {
+        let mut contract = setup();
+        contract.ft_transfer(accounts(2), U128(10), None);
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.set_store_receipts(false);
+        assert!(!contract.stores_receipts(accounts(2)));
+        assert!(contract.get_receipts(accounts(2), 0, 10).is_empty());
+        let receipts = get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, accounts(2));
+        match &receipts[0].actions[0] {
+            VmAction::Transfer { deposit } => assert_eq!(
+                *deposit,
+                Balance::from(RECEIPT_LOG_STORAGE_USAGE) * env::storage_byte_cost()
+            ),
+            action => panic!("Unexpected action {:?}", action),
+        }
+    }
+}
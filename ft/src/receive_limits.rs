@@ -0,0 +1,179 @@
+//! Receiver-side throttling of specific senders. An account can cap how much it accepts from a
+//! sender within a window, on top of the all-or-nothing receive consent. A window opens with the
+//! first transfer after the previous one expired, and transfers that would take the window's
+//! total above the cap are rejected.
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReceiveLimit {
+    #[serde(with = "crate::u128_dec_format")]
+    pub max_per_window: Balance,
+    pub window_ms: u64,
+    /// Block timestamp the current window opened at, in nanoseconds.
+    pub window_start: u64,
+    /// Amount received from the sender in the current window.
+    #[serde(with = "crate::u128_dec_format")]
+    pub received: Balance,
+}
+
+impl ReceiveLimit {
+    /// Returns the amount received in the window that is open now.
+    fn received_now(&self) -> Balance {
+        if env::block_timestamp() < self.window_start + self.window_ms * 1_000_000 {
+            self.received
+        } else {
+            0
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Caps what the caller accepts from `sender` to `max_per_window` every `window_ms`
+    /// milliseconds, starting a fresh window. Requires a deposit covering the storage of the
+    /// limit; any excess is refunded.
+    #[payable]
+    pub fn set_sender_receive_limit(
+        &mut self,
+        sender: AccountId,
+        max_per_window: U128,
+        window_ms: u64,
+    ) {
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        self.assert_registered(&account_id);
+        assert!(window_ms > 0, "The window should be positive");
+        let limit = ReceiveLimit {
+            max_per_window: max_per_window.into(),
+            window_ms,
+            window_start: 0,
+            received: 0,
+        };
+        self.receive_limits.insert(&(account_id, sender), &limit);
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    pub fn remove_sender_receive_limit(&mut self, sender: AccountId) {
+        self.receive_limits.remove(&(env::predecessor_account_id(), sender));
+    }
+
+    pub fn sender_receive_limit(
+        &self,
+        account_id: AccountId,
+        sender: AccountId,
+    ) -> Option<ReceiveLimit> {
+        self.receive_limits.get(&(account_id, sender))
+    }
+}
+
+impl Contract {
+    /// Checks the receive limit between the two accounts, counting `unrecorded` as already
+    /// received in the current window on top of the recorded transfers.
+    pub(crate) fn check_receive_limit(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        unrecorded: Balance,
+    ) -> Result<(), String> {
+        match self.receive_limits.get(&(receiver_id.clone(), sender_id.clone())) {
+            Some(limit) if limit.received_now() + unrecorded + amount > limit.max_per_window => {
+                Err(format!(
+                    "Receiver {} accepts at most {} from {} every {} ms",
+                    receiver_id, limit.max_per_window, sender_id, limit.window_ms
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a transfer of `amount` toward the receive limit between the two accounts, if any.
+    pub(crate) fn record_limited_receive(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) {
+        let key = (receiver_id.clone(), sender_id.clone());
+        if let Some(mut limit) = self.receive_limits.get(&key) {
+            if limit.received_now() == 0 {
+                limit.window_start = env::block_timestamp();
+                limit.received = 0;
+            }
+            limit.received += amount;
+            self.receive_limits.insert(&key, &limit);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    /// Charlie accepts at most 100 from bob per second; bob also funds danny.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        register(&mut contract, accounts(3));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(3), U128(1_000), None);
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.set_sender_receive_limit(accounts(1), U128(100), 1_000);
+        contract
+    }
+
+    fn transfer_at(contract: &mut Contract, sender: AccountId, amount: Balance, timestamp: u64) {
+        testing_env!(get_context(sender)
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_timestamp(timestamp)
+            .build());
+        contract.ft_transfer(accounts(2), U128(amount), None);
+    }
+
+    #[test]
+    fn test_within_limit() {
+        let mut contract = setup();
+        transfer_at(&mut contract, accounts(1), 60, 1_000_000_000);
+        transfer_at(&mut contract, accounts(1), 40, 1_500_000_000);
+        // A new window opens once the first one has expired.
+        transfer_at(&mut contract, accounts(1), 100, 2_000_000_000);
+        // Other senders are unaffected.
+        transfer_at(&mut contract, accounts(3), 500, 2_000_000_000);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 700);
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver charlie accepts at most 100 from bob every 1000 ms")]
+    fn test_over_limit() {
+        let mut contract = setup();
+        transfer_at(&mut contract, accounts(1), 60, 1_000_000_000);
+        transfer_at(&mut contract, accounts(1), 41, 1_999_999_999);
+    }
+
+    #[test]
+    fn test_simulated_transfers_share_the_limit() {
+        let contract = setup();
+        let op = (accounts(1), accounts(2), U128(60));
+        assert_eq!(
+            contract.simulate_transfers(vec![op.clone(), (accounts(3), accounts(2), U128(60)), op]),
+            vec![
+                Ok(U128(60)),
+                Ok(U128(60)),
+                Err("Receiver charlie accepts at most 100 from bob every 1000 ms".to_string()),
+            ]
+        );
+    }
+}
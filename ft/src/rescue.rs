@@ -0,0 +1,147 @@
+//! Recovery of unrelated NEP-141 tokens that were sent to this contract's account by mistake.
+use near_contract_standards::fungible_token::core::ext_ft_core;
+use near_sdk::{assert_one_yocto, is_promise_success, Gas, Promise};
+
+use crate::*;
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_RESCUE: Gas = Gas(5_000_000_000_000);
+
+#[near_bindgen]
+impl Contract {
+    /// Sends `amount` of the foreign token held by this contract at `token_contract` to
+    /// `receiver_id`. Only the owner can trigger a rescue, and ADH itself can never be rescued:
+    /// user balances live in this contract's state, not in a foreign token account.
+    #[payable]
+    pub fn rescue_ft(
+        &mut self,
+        token_contract: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(token_contract != env::current_account_id(), "ADH cannot be rescued");
+        ext_ft_core::ext(token_contract.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(
+                receiver_id.clone(),
+                amount,
+                Some("Rescue of tokens sent by mistake".to_string()),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_RESCUE)
+                    .resolve_rescue_ft(token_contract, receiver_id, amount),
+            )
+    }
+
+    /// Former name of `rescue_ft`, kept for existing callers.
+    #[payable]
+    pub fn rescue_foreign_ft(
+        &mut self,
+        token_contract: AccountId,
+        to: AccountId,
+        amount: U128,
+    ) -> Promise {
+        self.rescue_ft(token_contract, to, amount)
+    }
+
+    /// Logs the outcome of a rescue. Returns whether the foreign transfer succeeded.
+    #[private]
+    pub fn resolve_rescue_ft(
+        &mut self,
+        token_contract: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> bool {
+        let succeeded = is_promise_success();
+        if succeeded {
+            log!("Rescued {} of {} to @{}", amount.0, token_contract, receiver_id);
+        } else {
+            log!("Failed to rescue {} of {} to @{}", amount.0, token_contract, receiver_id);
+        }
+        succeeded
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs};
+    use near_sdk::{testing_env, PromiseResult, RuntimeFeesConfig, VMConfig};
+
+    use crate::tests::{get_context, TOTAL_SUPPLY};
+    use crate::*;
+
+    #[test]
+    fn test_rescue_schedules_foreign_transfer() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.rescue_ft(accounts(3), accounts(2), U128(42));
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, accounts(3));
+        match &receipts[0].actions[0] {
+            VmAction::FunctionCall { function_name, args, deposit, .. } => {
+                assert_eq!(function_name, "ft_transfer");
+                assert_eq!(*deposit, 1);
+                let args: near_sdk::serde_json::Value =
+                    near_sdk::serde_json::from_slice(args).unwrap();
+                assert_eq!(args["receiver_id"], "charlie");
+                assert_eq!(args["amount"], "42");
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+        assert_eq!(receipts[1].receiver_id, accounts(0));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_rescue_foreign_ft_alias() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.rescue_foreign_ft(accounts(3), accounts(2), U128(42));
+        assert_eq!(get_created_receipts()[0].receiver_id, accounts(3));
+    }
+
+    #[test]
+    fn test_resolve_rescue_failure() {
+        let context = get_context(accounts(0));
+        testing_env!(
+            context.build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        assert!(!contract.resolve_rescue_ft(accounts(3), accounts(2), U128(42)));
+        assert_eq!(get_logs().last().unwrap(), "Failed to rescue 42 of danny to @charlie");
+    }
+
+    #[test]
+    #[should_panic(expected = "ADH cannot be rescued")]
+    fn test_rescue_own_token() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.rescue_ft(accounts(0), accounts(2), U128(42));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_rescue_by_non_owner() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(2)).build());
+        contract.rescue_ft(accounts(3), accounts(2), U128(42));
+    }
+}
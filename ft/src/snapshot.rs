@@ -0,0 +1,218 @@
+//! Historical balances for governance. Voting power is read as of a past block height, so tokens
+//! moved between wallets after a vote's snapshot cannot be counted twice.
+//!
+//! Every transfer, mint and burn records a `(block_height, balance)` checkpoint for each affected
+//! account and for the total supply. Changes within the same block overwrite the block's
+//! checkpoint. A checkpoint that no proposal's snapshot block falls into is pruned as soon as a
+//! newer one is recorded, and proposal snapshots only ever move forward, so an account keeps at
+//! most its latest two checkpoints plus two per proposal it held a balance through. Transfers
+//! can't carry a storage deposit beyond NEP-141's 1 yoctoNEAR, so this pruning is what keeps the
+//! storage the contract pays for bounded by the proposals, which pay for themselves.
+//!
+//! Historical reads are therefore exact at the snapshot block of every proposal and from the
+//! latest proposal's snapshot onward; other past heights may read an older value.
+use near_sdk::collections::Vector;
+use near_sdk::BlockHeight;
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the balance of `account_id` at the end of block `block_height`.
+    pub fn ft_balance_of_at(&self, account_id: AccountId, block_height: BlockHeight) -> U128 {
+        self.snapshots
+            .get(&account_id)
+            .map_or(0, |checkpoints| checkpoint_at(&checkpoints, block_height))
+            .into()
+    }
+
+    /// Returns the total supply at the end of block `block_height`.
+    pub fn ft_total_supply_at(&self, block_height: BlockHeight) -> U128 {
+        checkpoint_at(&self.supply_snapshots, block_height).into()
+    }
+}
+
+impl Contract {
+    /// Checkpoints the current balance of each of `account_ids`.
+    pub(crate) fn record_balance_snapshots(&mut self, account_ids: &[&AccountId]) {
+        for account_id in account_ids {
+            let balance = self.token.accounts.get(account_id).unwrap_or(0);
+            let mut checkpoints = self.snapshots.get(account_id).unwrap_or_else(|| {
+                Vector::new([b"v".as_ref(), &env::sha256(account_id.as_bytes())].concat())
+            });
+            if record_checkpoint(&mut checkpoints, balance, self.last_proposal_snapshot) {
+                self.snapshots.insert(account_id, &checkpoints);
+            }
+        }
+    }
+
+    pub(crate) fn record_supply_snapshot(&mut self) {
+        record_checkpoint(
+            &mut self.supply_snapshots,
+            self.token.total_supply,
+            self.last_proposal_snapshot,
+        );
+    }
+}
+
+/// Records `value` as of the current block, pruning the second to last checkpoint when no
+/// proposal snapshot at or before `last_proposal_snapshot` reads it. Returns whether the
+/// checkpoints changed; an empty list already reads as zero, so recording zero into it is a no-op.
+fn record_checkpoint(
+    checkpoints: &mut Vector<(BlockHeight, Balance)>,
+    value: Balance,
+    last_proposal_snapshot: Option<BlockHeight>,
+) -> bool {
+    let block_height = env::block_height();
+    let len = checkpoints.len();
+    let (last_height, last_value) = match len {
+        0 => (None, 0),
+        _ => {
+            let (height, value) = checkpoints.get(len - 1).unwrap();
+            (Some(height), value)
+        }
+    };
+    if last_value == value {
+        return false;
+    }
+    if last_height == Some(block_height) {
+        checkpoints.replace(len - 1, &(block_height, value));
+        return true;
+    }
+    // The second to last checkpoint only answers reads before the last one, and future
+    // proposals snapshot after the last one, so it's unused unless a proposal snapshots into it.
+    let prunable = len >= 2
+        && match last_proposal_snapshot {
+            Some(snapshot) => snapshot < checkpoints.get(len - 2).unwrap().0,
+            None => true,
+        };
+    if prunable {
+        checkpoints.replace(len - 2, &(last_height.unwrap(), last_value));
+        checkpoints.replace(len - 1, &(block_height, value));
+    } else {
+        checkpoints.push(&(block_height, value));
+    }
+    true
+}
+
+/// Binary-searches for the last checkpoint at or before `block_height`. Zero before the first one.
+fn checkpoint_at(
+    checkpoints: &Vector<(BlockHeight, Balance)>,
+    block_height: BlockHeight,
+) -> Balance {
+    let (mut low, mut high) = (0, checkpoints.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if checkpoints.get(mid).unwrap().0 <= block_height {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    match low {
+        0 => 0,
+        _ => checkpoints.get(low - 1).unwrap().1,
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use near_sdk::BlockHeight;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    #[test]
+    fn test_balance_of_at() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_index(10).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+
+        for block_height in [20, 30] {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .block_index(block_height)
+                .build());
+            contract.ft_transfer(accounts(2), U128(100), None);
+        }
+
+        assert_eq!(contract.ft_balance_of_at(accounts(1), 9).0, 0);
+        assert_eq!(contract.ft_balance_of_at(accounts(1), 20).0, TOTAL_SUPPLY - 100);
+        assert_eq!(contract.ft_balance_of_at(accounts(1), 25).0, TOTAL_SUPPLY - 100);
+        assert_eq!(contract.ft_balance_of_at(accounts(2), 19).0, 0);
+        assert_eq!(contract.ft_balance_of_at(accounts(2), 29).0, 100);
+        assert_eq!(contract.ft_balance_of_at(accounts(2), 1_000).0, 200);
+        assert_eq!(contract.ft_balance_of_at(accounts(3), 1_000).0, 0);
+    }
+
+    #[test]
+    fn test_changes_within_a_block_keep_the_last_value() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_index(10).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_index(20)
+            .build());
+        contract.ft_transfer(accounts(2), U128(100), None);
+        contract.ft_transfer(accounts(2), U128(50), None);
+        assert_eq!(contract.ft_balance_of_at(accounts(2), 20).0, 150);
+        assert_eq!(contract.snapshots.get(&accounts(2)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_total_supply_at() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_index(10).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+
+        testing_env!(context.storage_usage(env::storage_usage()).block_index(20).build());
+        contract.ft_mint(accounts(1), U128(500), None);
+        assert_eq!(contract.ft_total_supply_at(9).0, 0);
+        assert_eq!(contract.ft_total_supply_at(19).0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_total_supply_at(20).0, TOTAL_SUPPLY + 500);
+        assert_eq!(contract.ft_balance_of_at(accounts(1), 20).0, TOTAL_SUPPLY + 500);
+    }
+
+    #[test]
+    fn test_checkpoints_are_pruned_outside_proposal_snapshots() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_index(10).build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        let transfer_at = |contract: &mut Contract, block_height: BlockHeight| {
+            testing_env!(get_context(accounts(1))
+                .storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .block_index(block_height)
+                .build());
+            contract.ft_transfer(accounts(2), U128(1), None);
+        };
+        for block_height in 20..30 {
+            transfer_at(&mut contract, block_height);
+        }
+        assert_eq!(contract.snapshots.get(&accounts(2)).unwrap().len(), 2);
+
+        // A proposal snapshotting block 29 keeps the checkpoint it reads.
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .block_index(30)
+            .build());
+        contract.create_proposal("Fund the grants program".to_string(), 100, None);
+        for block_height in 31..40 {
+            transfer_at(&mut contract, block_height);
+        }
+        assert_eq!(contract.snapshots.get(&accounts(2)).unwrap().len(), 4);
+        assert_eq!(contract.ft_balance_of_at(accounts(2), 29).0, 10);
+        assert_eq!(contract.ft_balance_of_at(accounts(2), 39).0, 19);
+    }
+}
@@ -0,0 +1,92 @@
+//! Owner-sponsored registration, so new users without NEAR can receive ADH. The owner or anyone
+//! else tops up a sponsorship budget, and the owner spends it on the storage deposits of accounts
+//! it registers. A sponsored deposit becomes the account's own storage balance: like any other,
+//! it is refunded to the account if it unregisters.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the attached deposit to the sponsorship budget.
+    #[payable]
+    pub fn fund_sponsorship(&mut self) -> U128 {
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "Attach a deposit to fund sponsorships");
+        self.sponsorship_budget += amount;
+        log!("@{} added {} to the sponsorship budget", env::predecessor_account_id(), amount);
+        self.sponsorship_budget.into()
+    }
+
+    /// Registers `account_id`, paying its storage deposit from the sponsorship budget. Only the
+    /// owner can sponsor registrations.
+    pub fn sponsor_register(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.token.accounts.contains_key(&account_id) {
+            log!("The account is already registered");
+            return;
+        }
+        let cost = self.storage_balance_bounds().min.0;
+        assert!(self.sponsorship_budget >= cost, "Sponsorship budget depleted");
+        self.sponsorship_budget -= cost;
+        self.internal_register_account(&account_id);
+        log!("Sponsored the registration of @{} for {}", account_id, cost);
+    }
+
+    pub fn sponsorship_budget(&self) -> U128 {
+        self.sponsorship_budget.into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, TOTAL_SUPPLY};
+    use crate::*;
+
+    /// Bob funds exactly one registration.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        let cost = contract.storage_balance_bounds().min.0;
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(cost).build());
+        assert_eq!(contract.fund_sponsorship().0, cost);
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(0).build());
+        contract
+    }
+
+    #[test]
+    fn test_sponsored_account_can_receive() {
+        let mut contract = setup();
+        contract.sponsor_register(accounts(2));
+        assert_eq!(contract.sponsorship_budget().0, 0);
+        assert_eq!(
+            contract.storage_balance_of(accounts(2)).unwrap().total,
+            contract.storage_balance_bounds().min
+        );
+
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer(accounts(2), U128(10), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Sponsorship budget depleted")]
+    fn test_depleted_budget() {
+        let mut contract = setup();
+        contract.sponsor_register(accounts(2));
+        contract.sponsor_register(accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_sponsor_by_non_owner() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2)).build());
+        contract.sponsor_register(accounts(2));
+    }
+}
@@ -0,0 +1,272 @@
+//! Staking ADH for a share of the rewards the owner funds. Staked tokens and the undistributed
+//! reward pool are held on the contract's own account, so they are neither transferable nor
+//! voting until unstaked and claimed.
+//!
+//! Rewards are distributed with a reward-per-token accumulator: every funding raises
+//! `reward_per_token` by `amount / total_staked`, and a stake earns its amount times the increase
+//! since it was last settled. Stakes are settled whenever they change, so funding stays O(1).
+//!
+//! Staking moves tokens like a transfer does, so the global pause also stops staking, unstaking,
+//! funding and claiming.
+
+use near_sdk::assert_one_yocto;
+
+use crate::*;
+
+pub(crate) mod uint256 {
+    #![allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+use uint256::U256;
+
+/// Fixed-point scale of `reward_per_token`.
+const REWARD_PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct Stake {
+    pub amount: Balance,
+    /// Block timestamp of the last change to the stake, in nanoseconds.
+    pub updated_at: u64,
+    /// `reward_per_token` when the stake was last settled.
+    reward_per_token_paid: u128,
+    /// Rewards settled but not claimed yet.
+    unclaimed_rewards: Balance,
+}
+
+impl Stake {
+    /// Credits the rewards earned since the last settlement.
+    fn settle(&mut self, reward_per_token: u128) {
+        let earned = U256::from(self.amount)
+            * U256::from(reward_per_token - self.reward_per_token_paid)
+            / U256::from(REWARD_PRECISION);
+        self.unclaimed_rewards += earned.as_u128();
+        self.reward_per_token_paid = reward_per_token;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Moves `amount` of the caller's free balance into its stake. Requires at least 1 yoctoNEAR,
+    /// plus the storage cost of a new stake; the excess is refunded.
+    #[payable]
+    pub fn stake(&mut self, amount: U128) {
+        self.assert_not_paused();
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        let contract_id = self.register_custody_account();
+        self.internal_transfer(&account_id, &contract_id, amount.0, Some("Stake".to_string()));
+        let mut stake = self.settled_stake(&account_id);
+        stake.amount += amount.0;
+        stake.updated_at = env::block_timestamp();
+        self.stakes.insert(&account_id, &stake);
+        self.total_staked += amount.0;
+        Self::charge_storage(initial_storage_usage);
+    }
+
+    /// Moves `amount` of the caller's stake back into its free balance. Requires 1 yoctoNEAR.
+    #[payable]
+    pub fn unstake(&mut self, amount: U128) {
+        assert_one_yocto();
+        self.assert_not_paused();
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        let account_id = env::predecessor_account_id();
+        let mut stake = self.settled_stake(&account_id);
+        assert!(stake.amount >= amount.0, "Not enough staked");
+        stake.amount -= amount.0;
+        stake.updated_at = env::block_timestamp();
+        self.save_stake(&account_id, &stake);
+        self.total_staked -= amount.0;
+        self.internal_transfer(
+            &env::current_account_id(),
+            &account_id,
+            amount.0,
+            Some("Unstake".to_string()),
+        );
+    }
+
+    /// Distributes `amount` of the owner's tokens to the current stakers pro rata. Requires
+    /// 1 yoctoNEAR.
+    #[payable]
+    pub fn fund_rewards(&mut self, amount: U128) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_not_paused();
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        assert!(self.total_staked > 0, "Nothing is staked");
+        let contract_id = self.register_custody_account();
+        self.internal_transfer(
+            &self.owner_id.clone(),
+            &contract_id,
+            amount.0,
+            Some("Staking rewards".to_string()),
+        );
+        let increase =
+            U256::from(amount.0) * U256::from(REWARD_PRECISION) / U256::from(self.total_staked);
+        self.reward_per_token += increase.as_u128();
+        self.reward_pool += amount.0;
+    }
+
+    /// Pays the caller's accrued rewards into its free balance.
+    pub fn claim_rewards(&mut self) -> U128 {
+        self.assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        let mut stake = self.settled_stake(&account_id);
+        let rewards = stake.unclaimed_rewards;
+        assert!(rewards > 0, "No rewards to claim");
+        stake.unclaimed_rewards = 0;
+        self.save_stake(&account_id, &stake);
+        self.reward_pool -= rewards;
+        self.internal_transfer(
+            &env::current_account_id(),
+            &account_id,
+            rewards,
+            Some("Staking rewards".to_string()),
+        );
+        rewards.into()
+    }
+
+    pub fn staked_balance_of(&self, account_id: AccountId) -> U128 {
+        self.stakes.get(&account_id).map_or(0, |stake| stake.amount).into()
+    }
+
+    pub fn pending_rewards(&self, account_id: AccountId) -> U128 {
+        self.settled_stake(&account_id).unclaimed_rewards.into()
+    }
+
+    pub fn total_staked(&self) -> U128 {
+        self.total_staked.into()
+    }
+}
+
+impl Contract {
+    fn settled_stake(&self, account_id: &AccountId) -> Stake {
+        let mut stake = self.stakes.get(account_id).unwrap_or_default();
+        stake.settle(self.reward_per_token);
+        stake
+    }
+
+    /// Stores `stake`, dropping it once nothing is left in it.
+    fn save_stake(&mut self, account_id: &AccountId, stake: &Stake) {
+        if stake.amount == 0 && stake.unclaimed_rewards == 0 {
+            self.stakes.remove(account_id);
+        } else {
+            self.stakes.insert(account_id, stake);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    /// Bob stakes 300 and gives charlie 1_000, who stakes 100.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), U128(1_000), None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.stake(U128(300));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.stake(U128(100));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract
+    }
+
+    #[test]
+    fn test_staked_tokens_leave_the_free_balance() {
+        let contract = setup();
+        assert_eq!(contract.staked_balance_of(accounts(2)).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 900);
+        assert_eq!(contract.total_staked().0, 400);
+        assert_eq!(
+            contract.simulate_transfers(vec![(accounts(2), accounts(1), U128(901))]),
+            vec![Err("The account doesn't have enough balance".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_rewards_are_shared_pro_rata() {
+        let mut contract = setup();
+        contract.fund_rewards(U128(400));
+        assert_eq!(contract.pending_rewards(accounts(1)).0, 300);
+        assert_eq!(contract.pending_rewards(accounts(2)).0, 100);
+
+        // A later stake only earns from later fundings.
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.stake(U128(200));
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.fund_rewards(U128(60));
+        assert_eq!(contract.pending_rewards(accounts(1)).0, 330);
+        assert_eq!(contract.pending_rewards(accounts(2)).0, 130);
+
+        testing_env!(get_context(accounts(2)).attached_deposit(1).build());
+        assert_eq!(contract.claim_rewards().0, 130);
+        assert_eq!(contract.pending_rewards(accounts(2)).0, 0);
+        contract.unstake(U128(300));
+        assert_eq!(contract.staked_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1_130);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough staked")]
+    fn test_unstake_more_than_staked() {
+        let mut contract = setup();
+        contract.unstake(U128(301));
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the storage cost of")]
+    fn test_stake_requires_storage_deposit() {
+        let mut contract = setup();
+        register(&mut contract, accounts(3));
+        testing_env!(get_context(accounts(1))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.ft_transfer(accounts(3), U128(100), None);
+        testing_env!(get_context(accounts(3))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.stake(U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfers are paused")]
+    fn test_unstake_while_paused() {
+        let mut contract = setup();
+        contract.set_paused(true);
+        contract.unstake(U128(300));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_fund_rewards_by_non_owner() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2)).attached_deposit(1).build());
+        contract.fund_rewards(U128(1));
+    }
+}
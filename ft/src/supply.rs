@@ -0,0 +1,107 @@
+//! Circulating supply for exchanges and aggregators. It is the total supply minus:
+//! - the balances of the accounts the owner excludes, e.g. the treasury;
+//! - everything held in custody on the contract's own account except staked tokens, i.e.
+//!   unreleased vesting, timelocked transfers, the unclaimed staking reward pool, the unclaimed
+//!   airdrop reserve and any tokens sent to the contract account directly.
+//!
+//! Staked tokens count as circulating: their holders can unstake them at any time. The view reads
+//! one balance per excluded account, and `MAX_EXCLUDED_ACCOUNTS` bounds their number.
+use crate::*;
+
+pub const MAX_EXCLUDED_ACCOUNTS: u64 = 10;
+
+#[near_bindgen]
+impl Contract {
+    pub fn ft_circulating_supply(&self) -> U128 {
+        let contract_id = env::current_account_id();
+        let custody = self.token.accounts.get(&contract_id).unwrap_or(0) - self.total_staked;
+        let excluded: Balance = self
+            .excluded_accounts
+            .iter()
+            .filter(|account_id| *account_id != contract_id)
+            .map(|account_id| self.token.accounts.get(&account_id).unwrap_or(0))
+            .sum();
+        (self.token.total_supply - custody - excluded).into()
+    }
+
+    /// Leaves the balance of `account_id` out of the circulating supply.
+    pub fn add_excluded_account(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        assert!(
+            self.excluded_accounts.len() < MAX_EXCLUDED_ACCOUNTS,
+            "Can't exclude more than {} accounts",
+            MAX_EXCLUDED_ACCOUNTS
+        );
+        if self.excluded_accounts.insert(&account_id) {
+            log!("Excluded @{} from the circulating supply", account_id);
+        }
+    }
+
+    pub fn remove_excluded_account(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.excluded_accounts.remove(&account_id) {
+            log!("Included @{} in the circulating supply", account_id);
+        }
+    }
+
+    pub fn get_excluded_accounts(&self) -> Vec<AccountId> {
+        self.excluded_accounts.to_vec()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{get_context, register, ONE_NEAR, TOTAL_SUPPLY};
+    use crate::*;
+
+    #[test]
+    fn test_circulating_supply() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        contract.set_min_lock_amount(U128(1));
+        register(&mut contract, accounts(2));
+        register(&mut contract, accounts(3));
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.grant_receive_consent(accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_timestamp(100)
+            .build());
+        contract.ft_transfer(accounts(2), U128(1_000), None);
+        contract.ft_transfer(accounts(3), U128(100), None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.transfer_with_lock(accounts(2), U128(30), 200);
+        contract.stake(U128(50));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.fund_rewards(U128(20));
+        assert_eq!(contract.ft_circulating_supply().0, TOTAL_SUPPLY - 30 - 20);
+
+        contract.add_excluded_account(accounts(2));
+        assert_eq!(contract.get_excluded_accounts(), vec![accounts(2)]);
+        assert_eq!(contract.ft_circulating_supply().0, TOTAL_SUPPLY - 30 - 20 - 1_000);
+
+        contract.remove_excluded_account(accounts(2));
+        assert_eq!(contract.ft_circulating_supply().0, TOTAL_SUPPLY - 30 - 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_exclude_by_non_owner() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.add_excluded_account(accounts(2));
+    }
+}
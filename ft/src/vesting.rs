@@ -0,0 +1,185 @@
+//! Linear vesting for team and investor allocations. The owner locks tokens on the contract's own
+//! account, from which the beneficiary claims them as they unlock. Locked tokens carry no votes.
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::*;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Times are block timestamps in nanoseconds; `cliff` and `duration` are offsets from `start`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingSchedule {
+    #[serde(with = "crate::u128_dec_format")]
+    pub total: Balance,
+    #[serde(with = "crate::u128_dec_format")]
+    pub released: Balance,
+    pub start: u64,
+    pub cliff: u64,
+    pub duration: u64,
+}
+
+impl VestingSchedule {
+    /// Amount unlocked at `timestamp`: nothing before the cliff, then linear up to `total` at the
+    /// end of `duration`.
+    fn vested_at(&self, timestamp: u64) -> Balance {
+        let elapsed = timestamp.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            0
+        } else if elapsed >= self.duration {
+            self.total
+        } else {
+            // `total * elapsed / duration` without overflowing u128.
+            let (elapsed, duration) = (elapsed as Balance, self.duration as Balance);
+            self.total / duration * elapsed + self.total % duration * elapsed / duration
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Locks `total` of the owner's tokens for `beneficiary`, unlocking linearly over
+    /// `duration_seconds` from now with nothing claimable during the first `cliff_seconds`.
+    pub fn create_vesting(
+        &mut self,
+        beneficiary: AccountId,
+        total: U128,
+        cliff_seconds: u64,
+        duration_seconds: u64,
+    ) {
+        self.assert_owner();
+        self.assert_registered(&beneficiary);
+        assert!(total.0 > 0, "The amount should be a positive number");
+        assert!(duration_seconds > 0, "The vesting duration should be positive");
+        assert!(cliff_seconds <= duration_seconds, "The cliff exceeds the vesting duration");
+        let duration = duration_seconds
+            .checked_mul(NANOS_PER_SECOND)
+            .expect("The vesting duration is too long");
+        // No larger than `duration`, as the cliff is at most the vesting duration.
+        let cliff = cliff_seconds * NANOS_PER_SECOND;
+        assert!(
+            self.vesting.get(&beneficiary).is_none(),
+            "The account {} already has a vesting schedule",
+            beneficiary
+        );
+        let contract_id = self.register_custody_account();
+        self.internal_transfer(
+            &self.owner_id.clone(),
+            &contract_id,
+            total.0,
+            Some(format!("Vesting for @{}", beneficiary)),
+        );
+        let schedule = VestingSchedule {
+            total: total.0,
+            released: 0,
+            start: env::block_timestamp(),
+            cliff,
+            duration,
+        };
+        self.vesting.insert(&beneficiary, &schedule);
+    }
+
+    /// Transfers everything unlocked and not yet released to the caller.
+    pub fn claim_vested(&mut self) -> U128 {
+        let beneficiary = env::predecessor_account_id();
+        let mut schedule = self.vesting.get(&beneficiary).expect("No vesting schedule");
+        let claimable = schedule.vested_at(env::block_timestamp()) - schedule.released;
+        assert!(claimable > 0, "Nothing to claim yet");
+        schedule.released += claimable;
+        self.vesting.insert(&beneficiary, &schedule);
+        self.internal_transfer(
+            &env::current_account_id(),
+            &beneficiary,
+            claimable,
+            Some("Vested tokens".to_string()),
+        );
+        claimable.into()
+    }
+
+    /// Returns the amount of the schedule of `account_id` unlocked so far, released or not.
+    pub fn vested_amount(&self, account_id: AccountId) -> U128 {
+        self.vesting
+            .get(&account_id)
+            .map_or(0, |schedule| schedule.vested_at(env::block_timestamp()))
+            .into()
+    }
+
+    pub fn get_vesting(&self, account_id: AccountId) -> Option<VestingSchedule> {
+        self.vesting.get(&account_id)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use super::NANOS_PER_SECOND;
+    use crate::tests::{get_context, register, TOTAL_SUPPLY};
+    use crate::*;
+
+    const START: u64 = 1_000 * NANOS_PER_SECOND;
+
+    /// Vests 1_000 tokens for charlie over 100 seconds with a 25 second cliff.
+    fn setup() -> Contract {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
+        register(&mut contract, accounts(2));
+        testing_env!(context.storage_usage(env::storage_usage()).block_timestamp(START).build());
+        contract.create_vesting(accounts(2), U128(1_000), 25, 100);
+        contract
+    }
+
+    fn claim_at(contract: &mut Contract, seconds: u64) -> Balance {
+        testing_env!(get_context(accounts(2))
+            .storage_usage(env::storage_usage())
+            .block_timestamp(START + seconds * NANOS_PER_SECOND)
+            .build());
+        contract.claim_vested().0
+    }
+
+    #[test]
+    fn test_create_vesting_locks_tokens() {
+        let contract = setup();
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 1_000);
+        assert_eq!(contract.vested_amount(accounts(2)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim yet")]
+    fn test_claim_before_cliff() {
+        let mut contract = setup();
+        claim_at(&mut contract, 24);
+    }
+
+    #[test]
+    fn test_claim_linearly() {
+        let mut contract = setup();
+        assert_eq!(claim_at(&mut contract, 25), 250);
+        assert_eq!(claim_at(&mut contract, 60), 350);
+        assert_eq!(contract.vested_amount(accounts(2)).0, 600);
+        assert_eq!(claim_at(&mut contract, 500), 400);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 0);
+        assert_eq!(contract.get_vesting(accounts(2)).unwrap().released, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_create_vesting_by_non_owner() {
+        let mut contract = setup();
+        testing_env!(get_context(accounts(2)).build());
+        contract.create_vesting(accounts(2), U128(1), 0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The vesting duration is too long")]
+    fn test_create_vesting_with_overflowing_duration() {
+        let mut contract = setup();
+        register(&mut contract, accounts(3));
+        testing_env!(get_context(accounts(1)).build());
+        contract.create_vesting(accounts(3), U128(1), 0, u64::MAX / NANOS_PER_SECOND + 1);
+    }
+}